@@ -65,10 +65,12 @@ async fn main() {
         // Using send() - fire-and-forget, never returns errors
         println!("Sending signal '{}' (fire-and-forget mode)...", cli.signal);
         client.send(&cli.signal, cli.user.as_deref(), None, Some(false), cli.float_value);
-        println!("✓ Signal dispatched (fire-and-forget)");
+        println!("✓ Signal queued (fire-and-forget)");
 
-        // Give the background task time to complete before the CLI exits
-        // In a long-running application, the tokio runtime would stay alive
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        // Deliver the queued signal before the CLI exits
+        // In a long-running application, the queue is flushed in the background
+        if let Err(e) = client.flush().await {
+            eprintln!("✗ Failed to flush queued signals: {}", e);
+        }
     }
 }
@@ -17,7 +17,8 @@ impl Component for Model {
     fn create(_ctx: &Context<Self>) -> Self {
         Self {
             value: 0,
-            telemetry: TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX"),
+            telemetry: TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+                .with_browser_params(),
         }
     }
 
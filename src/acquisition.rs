@@ -0,0 +1,163 @@
+//! Detection of the first run of an app
+//!
+//! The date of the first session is kept in a [`KeyValueStore`]. When it is missing, the
+//! current run is a new install:
+//! [`NEW_INSTALL_DETECTED`](crate::signals::acquisition::NEW_INSTALL_DETECTED) is sent
+//! once, before the first signal, and the date is stored once that announcement is
+//! queued.
+
+use crate::error::TelemetryDeckError;
+use crate::params;
+use crate::store::KeyValueStore;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Key of the first session date in the store
+const FIRST_SESSION_DATE_KEY: &str = params::acquisition::FIRST_SESSION_DATE;
+
+/// Outcome of [`detect_first_launch`]
+#[derive(Debug)]
+pub(crate) struct FirstLaunch {
+    /// Date of the first session, as `YYYY-MM-DD` (UTC)
+    pub(crate) first_session_date: String,
+    /// Set if this run is the first one
+    pub(crate) new_install: Option<PendingInstall>,
+}
+
+/// New install still to be announced and stored
+#[derive(Debug)]
+pub(crate) struct PendingInstall {
+    store: Arc<dyn KeyValueStore>,
+    first_session_date: String,
+}
+
+impl PendingInstall {
+    /// Store the first session date, so later runs are not new installs
+    ///
+    /// Failures are ignored: the next run then announces the install again.
+    pub(crate) fn remember(self) {
+        let _ = self
+            .store
+            .set(FIRST_SESSION_DATE_KEY, self.first_session_date);
+    }
+}
+
+/// Read the first session date from `store`, using `now` on the first run
+///
+/// Nothing is written: on the first run, the date is stored by
+/// [`PendingInstall::remember`].
+pub(crate) fn detect_first_launch(
+    store: Arc<dyn KeyValueStore>,
+    now: DateTime<Utc>,
+) -> Result<FirstLaunch, TelemetryDeckError> {
+    if let Some(first_session_date) = store.get(FIRST_SESSION_DATE_KEY)? {
+        return Ok(FirstLaunch {
+            first_session_date,
+            new_install: None,
+        });
+    }
+    let first_session_date = now.format("%Y-%m-%d").to_string();
+    Ok(FirstLaunch {
+        new_install: Some(PendingInstall {
+            store,
+            first_session_date: first_session_date.clone(),
+        }),
+        first_session_date,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::RecordingTransport;
+    use crate::{FixedClock, KeyValueStore, MemoryStore, TelemetryDeck, params, signals};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    fn client(store: &MemoryStore, clock: &FixedClock) -> (TelemetryDeck, RecordingTransport) {
+        let (builder, recorder) = TelemetryDeck::recording_builder("1234");
+        let client = builder
+            .clock(clock.clone())
+            .new_install_detection(store.clone())
+            .build()
+            .unwrap();
+        (client, recorder)
+    }
+
+    #[tokio::test]
+    async fn new_install_is_announced_once() {
+        let store = MemoryStore::new();
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (first_run, recorder) = client(&store, &clock);
+        first_run.send("appOpened", None, None, None, None);
+        first_run.send("appOpened", None, None, None, None);
+        first_run.flush().await.unwrap();
+
+        let types: Vec<_> = recorder
+            .signals()
+            .into_iter()
+            .map(|s| s.signal_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![
+                signals::acquisition::NEW_INSTALL_DETECTED,
+                "appOpened",
+                "appOpened"
+            ]
+        );
+        for signal in recorder.signals() {
+            assert_eq!(
+                signal.param(params::acquisition::FIRST_SESSION_DATE),
+                Some("2025-01-15")
+            );
+        }
+
+        clock.advance(Duration::from_secs(3 * 24 * 60 * 60));
+        let (second_run, recorder) = client(&store, &clock);
+        second_run.send("appOpened", None, None, None, None);
+        second_run.flush().await.unwrap();
+        recorder.assert_signal_not_sent(signals::acquisition::NEW_INSTALL_DETECTED);
+        recorder.assert_signal_sent_with(
+            "appOpened",
+            &[(params::acquisition::FIRST_SESSION_DATE, "2025-01-15")],
+        );
+    }
+
+    #[tokio::test]
+    async fn marker_is_written_once_the_announcement_is_queued() {
+        let store = MemoryStore::new();
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = client(&store, &clock);
+        client.signal("appOpened").build();
+        assert_eq!(
+            store.get(params::acquisition::FIRST_SESSION_DATE).unwrap(),
+            None
+        );
+
+        client.send("appOpened", None, None, None, None);
+        client.flush().await.unwrap();
+        recorder.assert_signal_sent(signals::acquisition::NEW_INSTALL_DETECTED);
+        assert_eq!(
+            store.get(params::acquisition::FIRST_SESSION_DATE).unwrap(),
+            Some("2025-01-15".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn announcement_has_user_and_test_mode_of_first_signal() {
+        let store = MemoryStore::new();
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = client(&store, &clock);
+        client
+            .signal("appOpened")
+            .user("alice")
+            .test_mode(true)
+            .send();
+        client.flush().await.unwrap();
+
+        let opened = recorder.assert_signal_sent("appOpened");
+        let announced = recorder.assert_signal_sent(signals::acquisition::NEW_INSTALL_DETECTED);
+        assert_eq!(announced.client_user, opened.client_user);
+        assert_eq!(announced.is_test_mode, "true");
+    }
+}
@@ -0,0 +1,306 @@
+//! Browser context parameters
+//!
+//! A [`BrowserEnvironment`] describes the browser running the client: language, screen,
+//! time zone and media query preferences. With the `wasm` feature, `WebBrowser` reads
+//! them from the `window` object; tests can describe any browser with their own
+//! implementation.
+
+use crate::core::TelemetryDeck;
+use crate::params;
+use crate::payload::PayloadValue;
+use std::collections::HashMap;
+use std::fmt;
+
+/// Source of the browser context
+///
+/// Every method returns `None` when the value is not available.
+///
+/// # Examples
+///
+/// ```
+/// use telemetrydeck_wasm::{BrowserEnvironment, TelemetryDeck, params};
+///
+/// /// A browser on a dark-themed phone
+/// #[derive(Debug)]
+/// struct FakeBrowser;
+///
+/// impl BrowserEnvironment for FakeBrowser {
+///     fn language(&self) -> Option<String> {
+///         Some("nl-BE".to_string())
+///     }
+///     fn screen_size(&self) -> Option<(u32, u32)> {
+///         Some((390, 844))
+///     }
+///     fn device_pixel_ratio(&self) -> Option<f64> {
+///         Some(3.0)
+///     }
+///     fn time_zone(&self) -> Option<String> {
+///         Some("Europe/Brussels".to_string())
+///     }
+///     fn matches_media(&self, query: &str) -> Option<bool> {
+///         Some(query == "(prefers-color-scheme: dark)")
+///     }
+/// }
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_browser_params_from(&FakeBrowser);
+/// assert_eq!(client.default_params()[params::user_preferences::COLOR_SCHEME], "Dark");
+/// ```
+pub trait BrowserEnvironment: fmt::Debug {
+    /// Preferred language as a BCP 47 tag (`navigator.language`)
+    fn language(&self) -> Option<String>;
+
+    /// Screen width and height in CSS pixels (`screen.width`, `screen.height`)
+    fn screen_size(&self) -> Option<(u32, u32)>;
+
+    /// Ratio of physical to CSS pixels (`devicePixelRatio`)
+    fn device_pixel_ratio(&self) -> Option<f64>;
+
+    /// IANA time zone (`Intl.DateTimeFormat().resolvedOptions().timeZone`)
+    fn time_zone(&self) -> Option<String>;
+
+    /// Whether a media query such as `(prefers-reduced-motion: reduce)` matches
+    fn matches_media(&self, query: &str) -> Option<bool>;
+}
+
+impl TelemetryDeck {
+    /// Add the browser context described by `environment` to the default parameters
+    ///
+    /// Sets, when available:
+    ///
+    /// - [`params::user_preferences::LANGUAGE`] (`nl`), [`params::user_preferences::REGION`]
+    ///   (`BE`) and [`params::run_context::LOCALE`] (`nl-BE`)
+    /// - [`params::device::SCREEN_WIDTH`], [`params::device::SCREEN_HEIGHT`] and
+    ///   [`params::device::SCREEN_DENSITY`]
+    /// - [`params::device::TIME_ZONE`]
+    /// - [`params::user_preferences::COLOR_SCHEME`] (`Dark` or `Light`)
+    /// - [`params::accessibility::IS_REDUCE_MOTION_ENABLED`]
+    ///
+    /// Default parameters that are already set are kept.
+    #[must_use]
+    pub fn with_browser_params_from(self, environment: &dyn BrowserEnvironment) -> Self {
+        let detected = browser_params(environment);
+        let mut state = self.write_state();
+        for (key, value) in detected {
+            state.default_params.entry(key).or_insert(value);
+        }
+        drop(state);
+        self
+    }
+}
+
+/// Default parameters describing `environment`
+fn browser_params(environment: &dyn BrowserEnvironment) -> HashMap<String, String> {
+    let mut result = HashMap::new();
+    let mut insert = |key: &str, value: PayloadValue| {
+        result.insert(key.to_string(), value.encode());
+    };
+
+    if let Some(language) = environment.language().filter(|tag| !tag.is_empty()) {
+        let mut subtags = language.split('-');
+        if let Some(primary) = subtags.next() {
+            insert(params::user_preferences::LANGUAGE, primary.into());
+        }
+        let region = subtags
+            .find(|subtag| subtag.len() == 2 && subtag.chars().all(|c| c.is_ascii_alphabetic()));
+        if let Some(region) = region {
+            insert(
+                params::user_preferences::REGION,
+                region.to_ascii_uppercase().into(),
+            );
+        }
+        insert(params::run_context::LOCALE, language.into());
+    }
+    if let Some((width, height)) = environment.screen_size() {
+        insert(params::device::SCREEN_WIDTH, width.into());
+        insert(params::device::SCREEN_HEIGHT, height.into());
+    }
+    if let Some(ratio) = environment.device_pixel_ratio() {
+        insert(params::device::SCREEN_DENSITY, ratio.into());
+    }
+    if let Some(time_zone) = environment.time_zone() {
+        insert(params::device::TIME_ZONE, time_zone.into());
+    }
+    if let Some(dark) = environment.matches_media("(prefers-color-scheme: dark)") {
+        insert(
+            params::user_preferences::COLOR_SCHEME,
+            if dark { "Dark" } else { "Light" }.into(),
+        );
+    }
+    if let Some(reduce) = environment.matches_media("(prefers-reduced-motion: reduce)") {
+        insert(
+            params::accessibility::IS_REDUCE_MOTION_ENABLED,
+            reduce.into(),
+        );
+    }
+    result
+}
+
+/// [`BrowserEnvironment`] of the browser running the client, read from `window`
+#[cfg(feature = "wasm")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct WebBrowser;
+
+#[cfg(feature = "wasm")]
+impl BrowserEnvironment for WebBrowser {
+    fn language(&self) -> Option<String> {
+        web_sys::window()?.navigator().language()
+    }
+
+    fn screen_size(&self) -> Option<(u32, u32)> {
+        let screen = web_sys::window()?.screen().ok()?;
+        let width = u32::try_from(screen.width().ok()?).ok()?;
+        let height = u32::try_from(screen.height().ok()?).ok()?;
+        Some((width, height))
+    }
+
+    fn device_pixel_ratio(&self) -> Option<f64> {
+        Some(web_sys::window()?.device_pixel_ratio())
+    }
+
+    fn time_zone(&self) -> Option<String> {
+        let options =
+            js_sys::Intl::DateTimeFormat::new(&js_sys::Array::new(), &js_sys::Object::new())
+                .resolved_options();
+        js_sys::Reflect::get(&options, &"timeZone".into())
+            .ok()?
+            .as_string()
+    }
+
+    fn matches_media(&self, query: &str) -> Option<bool> {
+        Some(web_sys::window()?.match_media(query).ok()??.matches())
+    }
+}
+
+#[cfg(feature = "wasm")]
+impl TelemetryDeck {
+    /// Add the context of the browser running the client to the default parameters
+    /// (`wasm` only)
+    ///
+    /// See [`with_browser_params_from`](Self::with_browser_params_from) for the detected
+    /// parameters.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// let client = TelemetryDeck::new("YOUR-APP-ID").with_browser_params();
+    /// ```
+    #[must_use]
+    pub fn with_browser_params(self) -> Self {
+        self.with_browser_params_from(&WebBrowser)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BrowserEnvironment;
+    use crate::{TelemetryDeck, params};
+
+    #[derive(Debug, Default)]
+    struct FakeBrowser {
+        language: Option<&'static str>,
+        screen_size: Option<(u32, u32)>,
+        device_pixel_ratio: Option<f64>,
+        time_zone: Option<&'static str>,
+        matching_media: Vec<&'static str>,
+    }
+
+    impl BrowserEnvironment for FakeBrowser {
+        fn language(&self) -> Option<String> {
+            self.language.map(str::to_string)
+        }
+
+        fn screen_size(&self) -> Option<(u32, u32)> {
+            self.screen_size
+        }
+
+        fn device_pixel_ratio(&self) -> Option<f64> {
+            self.device_pixel_ratio
+        }
+
+        fn time_zone(&self) -> Option<String> {
+            self.time_zone.map(str::to_string)
+        }
+
+        fn matches_media(&self, query: &str) -> Option<bool> {
+            Some(self.matching_media.contains(&query))
+        }
+    }
+
+    #[test]
+    fn maps_browser_context_to_params() {
+        let browser = FakeBrowser {
+            language: Some("nl-BE"),
+            screen_size: Some((1920, 1080)),
+            device_pixel_ratio: Some(2.0),
+            time_zone: Some("Europe/Brussels"),
+            matching_media: vec!["(prefers-reduced-motion: reduce)"],
+        };
+        let sut = TelemetryDeck::new("1234").with_browser_params_from(&browser);
+        let defaults = sut.default_params();
+        let expected = [
+            (params::user_preferences::LANGUAGE, "nl"),
+            (params::user_preferences::REGION, "BE"),
+            (params::run_context::LOCALE, "nl-BE"),
+            (params::device::SCREEN_WIDTH, "1920"),
+            (params::device::SCREEN_HEIGHT, "1080"),
+            (params::device::SCREEN_DENSITY, "2.0"),
+            (params::device::TIME_ZONE, "Europe/Brussels"),
+            (params::user_preferences::COLOR_SCHEME, "Light"),
+            (params::accessibility::IS_REDUCE_MOTION_ENABLED, "true"),
+        ];
+        for (key, value) in expected {
+            assert_eq!(defaults.get(key).map(String::as_str), Some(value), "{key}");
+        }
+    }
+
+    #[test]
+    fn missing_values_are_left_out() {
+        #[derive(Debug)]
+        struct NoBrowser;
+
+        impl BrowserEnvironment for NoBrowser {
+            fn language(&self) -> Option<String> {
+                None
+            }
+            fn screen_size(&self) -> Option<(u32, u32)> {
+                None
+            }
+            fn device_pixel_ratio(&self) -> Option<f64> {
+                None
+            }
+            fn time_zone(&self) -> Option<String> {
+                None
+            }
+            fn matches_media(&self, _query: &str) -> Option<bool> {
+                None
+            }
+        }
+
+        let sut = TelemetryDeck::new("1234");
+        let before = sut.default_params();
+        assert_eq!(
+            sut.with_browser_params_from(&NoBrowser).default_params(),
+            before
+        );
+    }
+
+    #[test]
+    fn explicit_defaults_are_kept() {
+        let browser = FakeBrowser {
+            language: Some("en"),
+            matching_media: vec!["(prefers-color-scheme: dark)"],
+            ..FakeBrowser::default()
+        };
+        let sut = TelemetryDeck::builder("1234")
+            .default_param(params::user_preferences::LANGUAGE, "fr")
+            .build()
+            .unwrap()
+            .with_browser_params_from(&browser);
+        let defaults = sut.default_params();
+        assert_eq!(defaults[params::user_preferences::LANGUAGE], "fr");
+        assert_eq!(defaults[params::user_preferences::COLOR_SCHEME], "Dark");
+        assert!(!defaults.contains_key(params::user_preferences::REGION));
+    }
+}
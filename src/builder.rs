@@ -0,0 +1,589 @@
+use crate::acquisition::detect_first_launch;
+use crate::calendar::CalendarTimeZone;
+#[cfg(not(feature = "wasm"))]
+use crate::client_native::default_transport;
+#[cfg(feature = "wasm")]
+use crate::client_wasm::default_transport;
+use crate::clock::{Clock, SystemClock};
+use crate::core::{ClientConfig, TelemetryDeck};
+use crate::error::TelemetryDeckError;
+use crate::ids::{IdGenerator, RandomIdGenerator};
+use crate::params;
+use crate::payload::PayloadValue;
+use crate::purchase::ExchangeRates;
+use crate::queue::BatchConfig;
+use crate::retention::RetentionTracker;
+use crate::retry::RetryPolicy;
+use crate::session::SessionConfig;
+#[cfg(not(feature = "wasm"))]
+use crate::spool::{Spool, SpoolConfig};
+use crate::store::KeyValueStore;
+use crate::transport::{HttpConfig, Transport};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Default base URL of the TelemetryDeck ingestion service
+pub(crate) const DEFAULT_URL: &str = "https://nom.telemetrydeck.com";
+
+/// Builder for a configured [`TelemetryDeck`] client
+///
+/// Created with [`TelemetryDeck::builder`]. Every setting is optional; [`build`](Self::build)
+/// validates the configuration and returns a [`TelemetryDeckError::InvalidConfiguration`]
+/// describing the first problem it finds.
+///
+/// # Examples
+///
+/// ```
+/// use telemetrydeck_wasm::TelemetryDeck;
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+/// let client = TelemetryDeck::builder("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+///     .url("https://telemetry-proxy.staging.example.com")
+///     .namespace("my-tenant")
+///     .salt("your-64-char-random-salt-here")
+///     .default_param("environment", "staging")
+///     .test_mode(true)
+///     .timeout(Duration::from_secs(10))
+///     .user_agent("my-app/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct TelemetryDeckBuilder {
+    app_id: String,
+    url: String,
+    namespace: Option<String>,
+    salt: Option<String>,
+    default_params: HashMap<String, String>,
+    session_id: Option<String>,
+    test_mode: bool,
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    batch_config: BatchConfig,
+    retry_policy: RetryPolicy,
+    transport: Option<Arc<dyn Transport>>,
+    clock: Option<Arc<dyn Clock>>,
+    ids: Option<Arc<dyn IdGenerator>>,
+    sessions: Option<SessionConfig>,
+    retention: Option<Arc<dyn KeyValueStore>>,
+    install_store: Option<Arc<dyn KeyValueStore>>,
+    calendar: Option<CalendarTimeZone>,
+    exchange_rates: Option<ExchangeRates>,
+    #[cfg(feature = "wasm")]
+    browser_params: bool,
+    #[cfg(not(feature = "wasm"))]
+    device_params: bool,
+    #[cfg(not(feature = "wasm"))]
+    spool: Option<SpoolConfig>,
+}
+
+impl TelemetryDeck {
+    /// Start configuring a new instance with the specified application id
+    ///
+    /// See [`TelemetryDeckBuilder`] for the available settings.
+    pub fn builder(app_id: &str) -> TelemetryDeckBuilder {
+        TelemetryDeckBuilder {
+            app_id: app_id.to_string(),
+            url: DEFAULT_URL.to_string(),
+            namespace: None,
+            salt: None,
+            default_params: HashMap::new(),
+            session_id: None,
+            test_mode: false,
+            timeout: None,
+            user_agent: None,
+            batch_config: BatchConfig::default(),
+            retry_policy: RetryPolicy::default(),
+            transport: None,
+            clock: None,
+            ids: None,
+            sessions: None,
+            retention: None,
+            install_store: None,
+            calendar: None,
+            exchange_rates: None,
+            #[cfg(feature = "wasm")]
+            browser_params: false,
+            #[cfg(not(feature = "wasm"))]
+            device_params: false,
+            #[cfg(not(feature = "wasm"))]
+            spool: None,
+        }
+    }
+}
+
+impl TelemetryDeckBuilder {
+    /// Base URL of the ingestion service
+    ///
+    /// Signals are posted to `{url}/v2/` (or `{url}/v2/namespace/{namespace}/`).
+    /// Default: `https://nom.telemetrydeck.com`
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Namespace for multi-tenant deployments
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Salt concatenated after user identifiers before hashing
+    pub fn salt(mut self, salt: impl Into<String>) -> Self {
+        self.salt = Some(salt.into());
+        self
+    }
+
+    /// Replace all default parameters appended to outgoing signals
+    pub fn default_params(mut self, params: HashMap<String, String>) -> Self {
+        self.default_params = params;
+        self
+    }
+
+    /// Add a single default parameter appended to outgoing signals
+    pub fn default_param(mut self, key: impl Into<String>, value: impl Into<PayloadValue>) -> Self {
+        self.default_params
+            .insert(key.into(), value.into().encode());
+        self
+    }
+
+    /// Initial session identifier
+    ///
+    /// Default: a random UUID v4
+    pub fn session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    /// Whether signals are marked as test signals when the caller does not say otherwise
+    ///
+    /// Default: `false`
+    pub fn test_mode(mut self, test_mode: bool) -> Self {
+        self.test_mode = test_mode;
+        self
+    }
+
+    /// Timeout for a single HTTP request
+    ///
+    /// Only applies to the default transport. Default: no timeout
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// `User-Agent` header sent with every request
+    ///
+    /// Default on native: `telemetrydeck-wasm/{version}`. On WebAssembly no header is sent
+    /// unless one is configured, since browsers set their own. Only applies to the default
+    /// transport.
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Batching configuration for fire-and-forget signals
+    pub fn batch_config(mut self, config: BatchConfig) -> Self {
+        self.batch_config = config;
+        self
+    }
+
+    /// Retry policy for failed deliveries
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = policy;
+        self
+    }
+
+    /// Custom transport used to post signals
+    ///
+    /// Replaces the default `reqwest` (native) or `reqwasm` (WebAssembly) transport;
+    /// [`timeout`](Self::timeout) and [`user_agent`](Self::user_agent) are then ignored.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    /// Clock timestamping new signals
+    ///
+    /// Default: [`SystemClock`](crate::SystemClock)
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Arc::new(clock));
+        self
+    }
+
+    /// Generator of session identifiers, used unless a [`session_id`](Self::session_id)
+    /// is set and by [`TelemetryDeck::reset_session`]
+    ///
+    /// Default: [`RandomIdGenerator`](crate::RandomIdGenerator)
+    pub fn id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        self.ids = Some(Arc::new(ids));
+        self
+    }
+
+    /// Start and renew sessions automatically
+    ///
+    /// See [`SessionConfig`]. Default: disabled, the session only changes through
+    /// [`TelemetryDeck::reset_session`].
+    pub fn session_tracking(mut self, config: SessionConfig) -> Self {
+        self.sessions = Some(config);
+        self
+    }
+
+    /// Compute retention parameters from the session history kept in `store`
+    ///
+    /// Requires [`session_tracking`](Self::session_tracking). Every tracked session is
+    /// recorded in the store, and when a session starts the following default parameters
+    /// are computed from that history:
+    ///
+    /// - [`TOTAL_SESSIONS_COUNT`](crate::params::retention::TOTAL_SESSIONS_COUNT):
+    ///   sessions started so far, including the current one
+    /// - [`DISTINCT_DAYS_USED`](crate::params::retention::DISTINCT_DAYS_USED): days (UTC)
+    ///   on which a session was started
+    /// - [`DISTINCT_DAYS_USED_LAST_MONTH`](crate::params::retention::DISTINCT_DAYS_USED_LAST_MONTH):
+    ///   the same, over the last 30 days
+    /// - [`AVERAGE_SESSION_SECONDS`](crate::params::retention::AVERAGE_SESSION_SECONDS):
+    ///   average duration of the previous sessions of the last 90 days
+    /// - [`PREVIOUS_SESSION_SECONDS`](crate::params::retention::PREVIOUS_SESSION_SECONDS):
+    ///   duration of the previous session, also when it belonged to an earlier run
+    ///
+    /// The duration of the current session is written to the store at most once a minute
+    /// and when the session ends, so the last session of a run may be recorded slightly
+    /// shorter than it was.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(not(feature = "wasm"))]
+    /// # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+    /// use telemetrydeck_wasm::{FileStore, SessionConfig, TelemetryDeck};
+    ///
+    /// let client = TelemetryDeck::builder("YOUR-APP-ID")
+    ///     .session_tracking(SessionConfig::default())
+    ///     .retention(FileStore::open("/var/lib/my-app/telemetry.json")?)
+    ///     .build()?;
+    /// # Ok(())
+    /// # }
+    /// # #[cfg(feature = "wasm")]
+    /// # fn main() {}
+    /// ```
+    pub fn retention(mut self, store: impl KeyValueStore + 'static) -> Self {
+        self.retention = Some(Arc::new(store));
+        self
+    }
+
+    /// Detect the first run of the app with the marker kept in `store`
+    ///
+    /// On the first run,
+    /// [`NEW_INSTALL_DETECTED`](crate::signals::acquisition::NEW_INSTALL_DETECTED) is sent
+    /// once, before the first signal, and the current date is stored once it is queued.
+    /// Every signal carries the stored date as
+    /// [`FIRST_SESSION_DATE`](crate::params::acquisition::FIRST_SESSION_DATE)
+    /// (`YYYY-MM-DD`, UTC).
+    ///
+    /// Use a [`FileStore`](crate::FileStore) on native targets; on WebAssembly, provide
+    /// a [`KeyValueStore`] backed by browser storage. To share one store with
+    /// [`retention`](Self::retention), pass it wrapped in an `Arc`.
+    pub fn new_install_detection(mut self, store: impl KeyValueStore + 'static) -> Self {
+        self.install_store = Some(Arc::new(store));
+        self
+    }
+
+    /// Add calendar parameters (day of week, hour of day...) computed in `time_zone`
+    ///
+    /// See [`TelemetryDeck::with_calendar_params`]. Default: disabled
+    pub fn calendar_params(mut self, time_zone: CalendarTimeZone) -> Self {
+        self.calendar = Some(time_zone);
+        self
+    }
+
+    /// Convert purchase prices to US dollars with `rates`
+    ///
+    /// See [`TelemetryDeck::with_exchange_rates`]. Default: [`ExchangeRates::bundled`]
+    pub fn exchange_rates(mut self, rates: ExchangeRates) -> Self {
+        self.exchange_rates = Some(rates);
+        self
+    }
+
+    /// Read the context of the browser running the client and add it to the default
+    /// parameters (`wasm` only)
+    ///
+    /// See [`TelemetryDeck::with_browser_params_from`]. Default parameters set on the
+    /// builder are kept.
+    #[cfg(feature = "wasm")]
+    pub fn browser_params(mut self) -> Self {
+        self.browser_params = true;
+        self
+    }
+
+    /// Detect device and operating system parameters and add them to the default
+    /// parameters (native only)
+    ///
+    /// See [`TelemetryDeck::with_device_params`]. Default parameters set on the builder
+    /// are kept.
+    #[cfg(not(feature = "wasm"))]
+    pub fn device_params(mut self) -> Self {
+        self.device_params = true;
+        self
+    }
+
+    /// Keep undelivered fire-and-forget signals on disk (native only)
+    ///
+    /// See [`SpoolConfig`] for details. Signals sent with `send_sync` are never spooled,
+    /// their errors are returned to the caller instead.
+    #[cfg(not(feature = "wasm"))]
+    pub fn spool(mut self, config: SpoolConfig) -> Self {
+        self.spool = Some(config);
+        self
+    }
+
+    /// Validate the configuration and create the client
+    ///
+    /// With a [`spool`](Self::spool), this also opens the spool directory and, when called
+    /// within a tokio runtime, starts sending the signals spooled by a previous run. With
+    /// [`new_install_detection`](Self::new_install_detection), this reads the first
+    /// session date, returning the store's error if that fails.
+    ///
+    /// The default HTTP client is only created when no [`transport`](Self::transport)
+    /// was given; if that fails, the error is returned as
+    /// [`TelemetryDeckError::Transport`].
+    pub fn build(self) -> Result<TelemetryDeck, TelemetryDeckError> {
+        self.validate()?;
+
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => default_transport(&HttpConfig {
+                timeout: self.timeout,
+                user_agent: self.user_agent,
+            })?,
+        };
+        let config = ClientConfig {
+            url: self.url.trim_end_matches('/').to_string(),
+            app_id: self.app_id,
+            namespace: self.namespace,
+            salt: self.salt,
+            retry_policy: self.retry_policy,
+            is_test_mode: self.test_mode,
+            transport,
+            clock: self.clock.unwrap_or_else(|| Arc::new(SystemClock)),
+            ids: self.ids.unwrap_or_else(|| Arc::new(RandomIdGenerator)),
+            #[cfg(not(feature = "wasm"))]
+            spool: self
+                .spool
+                .map(|spool| Spool::open(spool).map(Arc::new))
+                .transpose()?,
+            sessions: self.sessions,
+            retention: self
+                .retention
+                .map(|store| Arc::new(RetentionTracker::load(store))),
+            calendar: self.calendar,
+            exchange_rates: self.exchange_rates.map(Arc::new),
+        };
+        let mut client = TelemetryDeck::from_config(config, self.batch_config, self.default_params);
+        if let Some(session_id) = self.session_id {
+            client.reset_session(Some(session_id));
+        }
+        #[cfg(feature = "wasm")]
+        if self.browser_params {
+            client = client.with_browser_params();
+        }
+        #[cfg(not(feature = "wasm"))]
+        if self.device_params {
+            client = client.with_device_params();
+        }
+        if let Some(store) = self.install_store {
+            let first_launch = detect_first_launch(store, client.config.clock.now())?;
+            let mut state = client.write_state();
+            state.derived_params.insert(
+                params::acquisition::FIRST_SESSION_DATE.to_string(),
+                first_launch.first_session_date,
+            );
+            state.pending_install = first_launch.new_install;
+        }
+        #[cfg(not(feature = "wasm"))]
+        client.spawn_spool_replay();
+        Ok(client)
+    }
+
+    fn validate(&self) -> Result<(), TelemetryDeckError> {
+        let invalid = |message: &str| {
+            Err(TelemetryDeckError::InvalidConfiguration(
+                message.to_string(),
+            ))
+        };
+
+        if self.app_id.trim().is_empty() {
+            return invalid("app id must not be empty");
+        }
+        let host = self
+            .url
+            .strip_prefix("https://")
+            .or_else(|| self.url.strip_prefix("http://"));
+        match host {
+            Some(host)
+                if !host.trim_end_matches('/').is_empty()
+                    && !host.contains(char::is_whitespace) => {}
+            _ => return invalid("url must be an absolute http:// or https:// URL"),
+        }
+        if self.namespace.as_deref().is_some_and(|namespace| {
+            namespace.is_empty()
+                || namespace.contains(['/', '?', '#'])
+                || namespace.contains(char::is_whitespace)
+        }) {
+            return invalid("namespace must be a non-empty path segment");
+        }
+        if self.session_id.as_deref().is_some_and(str::is_empty) {
+            return invalid("session id must not be empty");
+        }
+        if self.timeout == Some(Duration::ZERO) {
+            return invalid("timeout must be greater than zero");
+        }
+        if self
+            .user_agent
+            .as_deref()
+            .is_some_and(|user_agent| user_agent.is_empty() || user_agent.contains(['\r', '\n']))
+        {
+            return invalid("user agent must be a non-empty single line");
+        }
+        if self.batch_config.max_batch_size == 0 {
+            return invalid("max batch size must be greater than zero");
+        }
+        if self.batch_config.max_queue_size < self.batch_config.max_batch_size {
+            return invalid("max queue size must not be smaller than the max batch size");
+        }
+        if self.retry_policy.max_attempts == 0 {
+            return invalid("retry policy must allow at least one attempt");
+        }
+        if !(0.0..=1.0).contains(&self.retry_policy.jitter) {
+            return invalid("retry jitter must be between 0.0 and 1.0");
+        }
+        if self.retry_policy.base_delay > self.retry_policy.max_delay {
+            return invalid("retry base delay must not exceed the max delay");
+        }
+        if self
+            .sessions
+            .is_some_and(|sessions| sessions.idle_timeout.is_zero())
+        {
+            return invalid("session idle timeout must be greater than zero");
+        }
+        if self.retention.is_some() && self.sessions.is_none() {
+            return invalid("retention tracking requires session tracking");
+        }
+        #[cfg(not(feature = "wasm"))]
+        if self
+            .spool
+            .as_ref()
+            .is_some_and(|spool| spool.max_size_bytes == 0 || spool.max_age.is_zero())
+        {
+            return invalid("spool size and max age must be greater than zero");
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        BatchConfig, MemoryStore, RetryPolicy, SessionConfig, TelemetryDeck, TelemetryDeckError,
+    };
+    use std::time::Duration;
+
+    fn assert_invalid(result: Result<TelemetryDeck, TelemetryDeckError>) {
+        assert!(matches!(
+            result,
+            Err(TelemetryDeckError::InvalidConfiguration(_))
+        ));
+    }
+
+    #[test]
+    fn build_with_defaults() {
+        let sut = TelemetryDeck::builder("1234").build().unwrap();
+        assert_eq!(sut.app_id(), "1234");
+        assert_eq!(sut.build_url(), "https://nom.telemetrydeck.com/v2/");
+        assert!(!sut.session_id().is_empty());
+    }
+
+    #[test]
+    fn build_with_custom_url_and_namespace() {
+        let sut = TelemetryDeck::builder("1234")
+            .url("http://localhost:8080/")
+            .namespace("staging")
+            .build()
+            .unwrap();
+        assert_eq!(
+            sut.build_url(),
+            "http://localhost:8080/v2/namespace/staging/"
+        );
+    }
+
+    #[test]
+    fn build_applies_settings() {
+        let sut = TelemetryDeck::builder("1234")
+            .salt("someSalt")
+            .default_param("environment", "staging")
+            .session_id("my session")
+            .test_mode(true)
+            .build()
+            .unwrap();
+        assert_eq!(sut.salt(), Some("someSalt"));
+        assert_eq!(sut.session_id(), "my session");
+        assert_eq!(
+            sut.default_params().get("environment").map(String::as_str),
+            Some("staging")
+        );
+
+        let signal = sut.create_signal("signal_type", None, None, None, None);
+        assert_eq!(signal.is_test_mode, "true");
+        let signal = sut.create_signal("signal_type", None, None, Some(false), None);
+        assert_eq!(signal.is_test_mode, "false");
+    }
+
+    #[test]
+    fn build_rejects_invalid_configuration() {
+        assert_invalid(TelemetryDeck::builder(" ").build());
+        assert_invalid(
+            TelemetryDeck::builder("1234")
+                .url("nom.telemetrydeck.com")
+                .build(),
+        );
+        assert_invalid(TelemetryDeck::builder("1234").url("https://").build());
+        assert_invalid(TelemetryDeck::builder("1234").namespace("a/b").build());
+        assert_invalid(TelemetryDeck::builder("1234").session_id("").build());
+        assert_invalid(
+            TelemetryDeck::builder("1234")
+                .timeout(Duration::ZERO)
+                .build(),
+        );
+        assert_invalid(TelemetryDeck::builder("1234").user_agent("a\nb").build());
+        assert_invalid(
+            TelemetryDeck::builder("1234")
+                .batch_config(BatchConfig {
+                    max_batch_size: 0,
+                    ..BatchConfig::default()
+                })
+                .build(),
+        );
+        assert_invalid(
+            TelemetryDeck::builder("1234")
+                .retry_policy(RetryPolicy {
+                    jitter: 1.5,
+                    ..RetryPolicy::default()
+                })
+                .build(),
+        );
+        assert_invalid(
+            TelemetryDeck::builder("1234")
+                .session_tracking(SessionConfig {
+                    idle_timeout: Duration::ZERO,
+                })
+                .build(),
+        );
+        assert_invalid(
+            TelemetryDeck::builder("1234")
+                .retention(MemoryStore::new())
+                .build(),
+        );
+    }
+}
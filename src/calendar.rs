@@ -0,0 +1,139 @@
+//! Calendar parameters derived from the time a signal was created
+//!
+//! When enabled, every signal carries all of [`params::calendar`], computed from its
+//! `received_at` timestamp in the configured [`CalendarTimeZone`]. Default parameters
+//! and per-signal parameters with the same keys take precedence.
+
+use crate::params;
+use chrono::{DateTime, Datelike, FixedOffset, Local, Timelike, Utc, Weekday};
+
+/// Time zone in which calendar parameters are computed
+///
+/// # Examples
+///
+/// ```no_run
+/// use chrono::FixedOffset;
+/// use telemetrydeck_wasm::{CalendarTimeZone, TelemetryDeck};
+///
+/// // Hour of day and weekends as seen by the user
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_calendar_params(CalendarTimeZone::Local);
+///
+/// // Or as seen in a fixed time zone, for example the one of a server's users
+/// let offset = FixedOffset::east_opt(2 * 60 * 60).unwrap();
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_calendar_params(CalendarTimeZone::Fixed(offset));
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CalendarTimeZone {
+    /// Time zone of the device running the client (the browser's with the `wasm` feature)
+    #[default]
+    Local,
+    /// Coordinated Universal Time
+    Utc,
+    /// Fixed offset from UTC
+    Fixed(FixedOffset),
+}
+
+impl CalendarTimeZone {
+    fn localize(self, at: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match self {
+            CalendarTimeZone::Local => at.with_timezone(&Local).fixed_offset(),
+            CalendarTimeZone::Utc => at.fixed_offset(),
+            CalendarTimeZone::Fixed(offset) => at.with_timezone(&offset),
+        }
+    }
+}
+
+/// Calendar parameters of a signal created at `at`
+///
+/// Days of the week are numbered from Monday (1) to Sunday (7), and weeks follow ISO 8601.
+pub(crate) fn calendar_params(
+    at: DateTime<Utc>,
+    time_zone: CalendarTimeZone,
+) -> [(&'static str, String); 8] {
+    let local = time_zone.localize(at);
+    let weekday = local.weekday();
+    [
+        (params::calendar::DAY_OF_MONTH, local.day().to_string()),
+        (
+            params::calendar::DAY_OF_WEEK,
+            weekday.number_from_monday().to_string(),
+        ),
+        (params::calendar::DAY_OF_YEAR, local.ordinal().to_string()),
+        (
+            params::calendar::WEEK_OF_YEAR,
+            local.iso_week().week().to_string(),
+        ),
+        (
+            params::calendar::IS_WEEKEND,
+            matches!(weekday, Weekday::Sat | Weekday::Sun).to_string(),
+        ),
+        (params::calendar::MONTH_OF_YEAR, local.month().to_string()),
+        (
+            params::calendar::QUARTER_OF_YEAR,
+            (local.month0() / 3 + 1).to_string(),
+        ),
+        (params::calendar::HOUR_OF_DAY, local.hour().to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CalendarTimeZone;
+    use crate::{FixedClock, TelemetryDeck, params};
+    use chrono::{FixedOffset, TimeZone, Utc};
+
+    fn client(time_zone: CalendarTimeZone) -> TelemetryDeck {
+        // Saturday evening in UTC, already Sunday east of UTC
+        let now = Utc.with_ymd_and_hms(2025, 1, 18, 23, 30, 0).unwrap();
+        TelemetryDeck::new("1234")
+            .with_clock(FixedClock::new(now))
+            .with_calendar_params(time_zone)
+    }
+
+    #[test]
+    fn calendar_params_in_utc() {
+        let signal = client(CalendarTimeZone::Utc).signal("tick").build();
+        let expected = [
+            (params::calendar::DAY_OF_MONTH, "18"),
+            (params::calendar::DAY_OF_WEEK, "6"),
+            (params::calendar::DAY_OF_YEAR, "18"),
+            (params::calendar::WEEK_OF_YEAR, "3"),
+            (params::calendar::IS_WEEKEND, "true"),
+            (params::calendar::MONTH_OF_YEAR, "1"),
+            (params::calendar::QUARTER_OF_YEAR, "1"),
+            (params::calendar::HOUR_OF_DAY, "23"),
+        ];
+        for (key, value) in expected {
+            assert_eq!(signal.param(key), Some(value), "{key}");
+        }
+    }
+
+    #[test]
+    fn calendar_params_in_fixed_offset() {
+        let offset = FixedOffset::east_opt(2 * 60 * 60).unwrap();
+        let signal = client(CalendarTimeZone::Fixed(offset))
+            .signal("tick")
+            .build();
+        assert_eq!(signal.param(params::calendar::DAY_OF_MONTH), Some("19"));
+        assert_eq!(signal.param(params::calendar::DAY_OF_WEEK), Some("7"));
+        assert_eq!(signal.param(params::calendar::HOUR_OF_DAY), Some("1"));
+    }
+
+    #[test]
+    fn explicit_params_take_precedence() {
+        let sut = client(CalendarTimeZone::Utc);
+        sut.set_default_param(params::calendar::HOUR_OF_DAY, 12);
+        let signal = sut
+            .signal("tick")
+            .param(params::calendar::IS_WEEKEND, false)
+            .build();
+        assert_eq!(signal.param(params::calendar::HOUR_OF_DAY), Some("12"));
+        assert_eq!(signal.param(params::calendar::IS_WEEKEND), Some("false"));
+    }
+
+    #[test]
+    fn disabled_by_default() {
+        let signal = TelemetryDeck::new("1234").signal("tick").build();
+        assert_eq!(signal.param(params::calendar::HOUR_OF_DAY), None);
+    }
+}
@@ -1,11 +1,21 @@
 use crate::core::{Signal, TelemetryDeck};
+use crate::error::TelemetryDeckError;
+use crate::queue::{QueueAction, SignalQueue};
+use crate::retry::{RetryPolicy, parse_retry_after, with_retries};
+use crate::spool::Spool;
+use crate::transport::{HttpConfig, Transport, TransportFuture};
+use chrono::Utc;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 
 impl TelemetryDeck {
     /// Send a telemetry signal (fire-and-forget)
     ///
-    /// This method spawns an async task using `tokio::spawn` and never returns errors.
-    /// The signal is sent in the background without blocking. Use [`send_sync`](Self::send_sync)
+    /// The signal is added to the client's queue and never returns errors. Queued signals
+    /// are posted together in the background using `tokio::spawn`, once the batch is full or
+    /// the flush interval has elapsed (see [`BatchConfig`](crate::BatchConfig)).
+    /// Use [`flush`](Self::flush) to deliver them right away, or [`send_sync`](Self::send_sync)
     /// if you need error handling.
     ///
     /// # Parameters
@@ -14,7 +24,8 @@ impl TelemetryDeck {
     /// * `client_user` - Optional user identifier. Will be SHA-256 hashed automatically.
     ///   If `None`, defaults to "rust".
     /// * `payload` - Optional key-value parameters to attach to the signal
-    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to `false` if `None`.
+    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to the client's
+    ///   test mode (`false` unless configured) if `None`.
     /// * `float_value` - Optional floating-point value (useful for metrics like revenue, duration, etc.)
     ///
     /// # Examples
@@ -58,8 +69,13 @@ impl TelemetryDeck {
         is_test_mode: Option<bool>,
         float_value: Option<f64>,
     ) {
-        let signal =
-            self.create_signal(signal_type, client_user, payload, is_test_mode, float_value);
+        let signal = self.create_outgoing_signal(
+            signal_type,
+            client_user,
+            payload,
+            is_test_mode,
+            float_value,
+        );
         self.send_one(signal);
     }
 
@@ -74,13 +90,15 @@ impl TelemetryDeck {
     /// * `client_user` - Optional user identifier. Will be SHA-256 hashed automatically.
     ///   If `None`, defaults to "rust".
     /// * `payload` - Optional key-value parameters to attach to the signal
-    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to `false` if `None`.
+    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to the client's
+    ///   test mode (`false` unless configured) if `None`.
     /// * `float_value` - Optional floating-point value (useful for metrics like revenue, duration, etc.)
     ///
     /// # Returns
     ///
     /// * `Ok(())` if the signal was sent successfully (HTTP 2xx status)
-    /// * `Err(...)` if sending failed (network error, HTTP error, serialization error, etc.)
+    /// * `Err(...)` with a [`TelemetryDeckError`] describing what failed (network error,
+    ///   HTTP status, serialization error, etc.), after retries were exhausted
     ///
     /// # Examples
     ///
@@ -108,45 +126,267 @@ impl TelemetryDeck {
         payload: Option<HashMap<String, String>>,
         is_test_mode: Option<bool>,
         float_value: Option<f64>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let signal =
-            self.create_signal(signal_type, client_user, payload, is_test_mode, float_value);
+    ) -> Result<(), TelemetryDeckError> {
+        let signal = self.create_outgoing_signal(
+            signal_type,
+            client_user,
+            payload,
+            is_test_mode,
+            float_value,
+        );
         self.send_many_sync(vec![signal]).await
     }
 
-    fn send_one(&self, signal: Signal) {
-        self.send_many(vec![signal])
+    /// Deliver all queued signals and wait for the result
+    ///
+    /// Returns once every signal queued before the call, including batches already
+    /// being posted in the background, has been delivered or has failed.
+    /// If several batches fail, the first error is returned. Signals dropped because the
+    /// queue was full since the previous call are reported as
+    /// [`TelemetryDeckError::QueueOverflow`], even if they were dropped before a background
+    /// flush.
+    ///
+    /// With an offline spool (see [`TelemetryDeckBuilder::spool`](crate::TelemetryDeckBuilder::spool)),
+    /// batches that failed with a retryable error are kept on disk, and once every batch
+    /// was delivered the previously spooled signals are sent as well.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TelemetryDeck::new("YOUR-APP-ID");
+    ///
+    /// client.send("jobStarted", None, None, None, None);
+    /// client.send("jobFinished", None, None, None, None);
+    ///
+    /// // Make sure nothing is lost before the process exits
+    /// client.flush().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn flush(&self) -> Result<(), TelemetryDeckError> {
+        let result = self.delivery().flush(&self.queue).await;
+        self.queue.take_overflow().and(result)
+    }
+
+    pub(crate) fn send_one(&self, signal: Signal) {
+        match self.queue.push(signal) {
+            QueueAction::FlushNow => self.spawn_flush(None),
+            QueueAction::ScheduleFlush => {
+                self.spawn_flush(Some(self.queue.config().flush_interval));
+            }
+            QueueAction::None => {}
+        }
+    }
+
+    fn spawn_flush(&self, delay: Option<Duration>) {
+        let queue = Arc::clone(&self.queue);
+        let delivery = self.delivery();
+        tokio::spawn(async move {
+            if let Some(delay) = delay {
+                tokio::time::sleep(delay).await;
+            }
+            let _ = delivery.flush(&queue).await;
+        });
     }
 
-    fn send_many(&self, signals: Vec<Signal>) {
-        let url = self.build_url();
+    /// Send the signals spooled by a previous run in the background
+    ///
+    /// Does nothing outside of a tokio runtime; the spool is then replayed by the next
+    /// successful flush.
+    pub(crate) fn spawn_spool_replay(&self) {
+        let delivery = self.delivery();
+        let Some(spool) = delivery.spool.clone() else {
+            return;
+        };
+        if spool.is_empty() || tokio::runtime::Handle::try_current().is_err() {
+            return;
+        }
         tokio::spawn(async move {
-            let client = reqwest::Client::new();
-            let body = serde_json::to_string(&signals).unwrap();
-            let _resp = client
-                .post(&url)
-                .body(body)
-                .header("Content-Type", "application/json")
-                .send()
-                .await;
+            let _ = spool.replay(|signals| delivery.clone().send(signals)).await;
         });
     }
 
-    async fn send_many_sync(&self, signals: Vec<Signal>) -> Result<(), Box<dyn std::error::Error>> {
-        let url = self.build_url();
-        let client = reqwest::Client::new();
-        let body = serde_json::to_string(&signals)?;
-        let resp = client
-            .post(&url)
-            .body(body)
-            .header("Content-Type", "application/json")
-            .send()
+    fn delivery(&self) -> Delivery {
+        Delivery {
+            url: self.build_url(),
+            policy: self.config.retry_policy,
+            transport: Arc::clone(&self.config.transport),
+            spool: self.config.spool.clone(),
+        }
+    }
+
+    pub(crate) async fn send_many_sync(
+        &self,
+        signals: Vec<Signal>,
+    ) -> Result<(), TelemetryDeckError> {
+        send_many(
+            &*self.config.transport,
+            &self.build_url(),
+            signals,
+            self.config.retry_policy,
+        )
+        .await
+    }
+}
+
+/// Everything needed to deliver batches in the background
+#[derive(Debug, Clone)]
+struct Delivery {
+    url: String,
+    policy: RetryPolicy,
+    transport: Arc<dyn Transport>,
+    spool: Option<Arc<Spool>>,
+}
+
+impl Delivery {
+    async fn send(self, signals: Vec<Signal>) -> Result<(), TelemetryDeckError> {
+        send_many(&*self.transport, &self.url, signals, self.policy).await
+    }
+
+    /// Send a batch, keeping it in the spool if it could be delivered later
+    async fn send_or_spool(self, signals: Vec<Signal>) -> Result<(), TelemetryDeckError> {
+        let Some(spool) = self.spool.clone() else {
+            return self.send(signals).await;
+        };
+        let backup = signals.clone();
+        let result = self.send(signals).await;
+        if result.as_ref().is_err_and(TelemetryDeckError::is_retryable) {
+            // The delivery error is more useful to the caller than a failure to spool
+            let _ = spool.store(&backup);
+        }
+        result
+    }
+
+    /// Flush the queue, then replay the spool if every batch was delivered
+    async fn flush(self, queue: &SignalQueue) -> Result<(), TelemetryDeckError> {
+        queue
+            .flush(|signals| self.clone().send_or_spool(signals))
             .await?;
+        match &self.spool {
+            Some(spool) => spool.replay(|signals| self.clone().send(signals)).await,
+            None => Ok(()),
+        }
+    }
+}
+
+async fn send_many(
+    transport: &dyn Transport,
+    url: &str,
+    signals: Vec<Signal>,
+    policy: RetryPolicy,
+) -> Result<(), TelemetryDeckError> {
+    let body = serde_json::to_string(&signals)?;
+    with_retries(
+        policy,
+        || transport.post(url, body.clone()),
+        tokio::time::sleep,
+    )
+    .await
+}
+
+pub(crate) fn default_transport(
+    http: &HttpConfig,
+) -> Result<Arc<dyn Transport>, TelemetryDeckError> {
+    Ok(Arc::new(ReqwestTransport::from_config(http)?))
+}
+
+/// [`Transport`] posting signals with `reqwest`, the default on native targets
+///
+/// # Examples
+///
+/// Use your own `reqwest` client to add headers, a proxy or custom TLS settings:
+///
+/// ```no_run
+/// use reqwest::header::{AUTHORIZATION, HeaderMap, HeaderValue};
+/// use telemetrydeck_wasm::{ReqwestTransport, TelemetryDeck};
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer proxy-token"));
+/// let http = reqwest::Client::builder()
+///     .default_headers(headers)
+///     .build()
+///     .unwrap();
+///
+/// let client = TelemetryDeck::builder("YOUR-APP-ID")
+///     .url("https://telemetry-proxy.example.com")
+///     .transport(ReqwestTransport::with_client(http))
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
 
-        if resp.status().is_success() {
-            Ok(())
-        } else {
-            Err(format!("HTTP error: {}", resp.status()).into())
+impl ReqwestTransport {
+    /// Transport with the default user agent and no timeout
+    ///
+    /// # Panics
+    ///
+    /// Panics if the TLS backend cannot be initialized, like `reqwest::Client::new`.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::from_config(&HttpConfig::default()).expect("failed to create the HTTP client")
+    }
+
+    /// Transport posting with an existing `reqwest` client
+    #[must_use]
+    pub fn with_client(client: reqwest::Client) -> Self {
+        ReqwestTransport { client }
+    }
+
+    pub(crate) fn from_config(http: &HttpConfig) -> Result<Self, TelemetryDeckError> {
+        let mut client = reqwest::Client::builder().user_agent(
+            http.user_agent
+                .clone()
+                .unwrap_or_else(|| format!("telemetrydeck-wasm/{}", env!("CARGO_PKG_VERSION"))),
+        );
+        if let Some(timeout) = http.timeout {
+            client = client.timeout(timeout);
         }
+        let client = client
+            .build()
+            .map_err(|e| TelemetryDeckError::Transport(e.to_string()))?;
+        Ok(ReqwestTransport { client })
+    }
+}
+
+impl Default for ReqwestTransport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn post<'a>(&'a self, url: &'a str, body: String) -> TransportFuture<'a> {
+        Box::pin(post(&self.client, url, body))
+    }
+}
+
+async fn post(client: &reqwest::Client, url: &str, body: String) -> Result<(), TelemetryDeckError> {
+    let resp = client
+        .post(url)
+        .body(body)
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .map_err(|e| TelemetryDeckError::Transport(e.to_string()))?;
+
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        let retry_after = resp
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_retry_after(value, Utc::now()));
+        Err(TelemetryDeckError::HttpStatus {
+            status: resp.status().as_u16(),
+            body: resp.text().await.unwrap_or_default(),
+            retry_after,
+        })
     }
 }
@@ -1,13 +1,24 @@
 use crate::core::{Signal, TelemetryDeck};
+use crate::error::TelemetryDeckError;
+use crate::queue::QueueAction;
+use crate::retry::{RetryPolicy, parse_retry_after, with_retries};
+use crate::transport::{HttpConfig, Transport, TransportFuture};
+use chrono::Utc;
+use futures::future::{Either, select};
+use gloo_timers::future::sleep;
 use reqwasm::http::Request;
 use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
 use wasm_bindgen_futures::spawn_local;
 
 impl TelemetryDeck {
     /// Send a telemetry signal (fire-and-forget)
     ///
-    /// This method spawns an async task using `spawn_local` and never returns errors.
-    /// The signal is sent in the background without blocking. Use [`send_sync`](Self::send_sync)
+    /// The signal is added to the client's queue and never returns errors. Queued signals
+    /// are posted together in the background using `spawn_local`, once the batch is full or
+    /// the flush interval has elapsed (see [`BatchConfig`](crate::BatchConfig)).
+    /// Use [`flush`](Self::flush) to deliver them right away, or [`send_sync`](Self::send_sync)
     /// if you need error handling.
     ///
     /// # Parameters
@@ -16,7 +27,8 @@ impl TelemetryDeck {
     /// * `client_user` - Optional user identifier. Will be SHA-256 hashed automatically.
     ///   If `None`, defaults to "rust".
     /// * `payload` - Optional key-value parameters to attach to the signal
-    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to `false` if `None`.
+    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to the client's
+    ///   test mode (`false` unless configured) if `None`.
     /// * `float_value` - Optional floating-point value (useful for metrics like revenue, duration, etc.)
     ///
     /// # Examples
@@ -60,8 +72,13 @@ impl TelemetryDeck {
         is_test_mode: Option<bool>,
         float_value: Option<f64>,
     ) {
-        let signal =
-            self.create_signal(signal_type, client_user, payload, is_test_mode, float_value);
+        let signal = self.create_outgoing_signal(
+            signal_type,
+            client_user,
+            payload,
+            is_test_mode,
+            float_value,
+        );
         self.send_one(signal);
     }
 
@@ -76,13 +93,15 @@ impl TelemetryDeck {
     /// * `client_user` - Optional user identifier. Will be SHA-256 hashed automatically.
     ///   If `None`, defaults to "rust".
     /// * `payload` - Optional key-value parameters to attach to the signal
-    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to `false` if `None`.
+    /// * `is_test_mode` - Whether to mark this as a test signal. Defaults to the client's
+    ///   test mode (`false` unless configured) if `None`.
     /// * `float_value` - Optional floating-point value (useful for metrics like revenue, duration, etc.)
     ///
     /// # Returns
     ///
     /// * `Ok(())` if the signal was sent successfully (HTTP 2xx status)
-    /// * `Err(...)` if sending failed (network error, HTTP error, serialization error, etc.)
+    /// * `Err(...)` with a [`TelemetryDeckError`] describing what failed (network error,
+    ///   HTTP status, serialization error, etc.), after retries were exhausted
     ///
     /// # Examples
     ///
@@ -110,41 +129,206 @@ impl TelemetryDeck {
         payload: Option<HashMap<String, String>>,
         is_test_mode: Option<bool>,
         float_value: Option<f64>,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let signal =
-            self.create_signal(signal_type, client_user, payload, is_test_mode, float_value);
+    ) -> Result<(), TelemetryDeckError> {
+        let signal = self.create_outgoing_signal(
+            signal_type,
+            client_user,
+            payload,
+            is_test_mode,
+            float_value,
+        );
         self.send_many_sync(vec![signal]).await
     }
 
-    fn send_one(&self, signal: Signal) {
-        self.send_many(vec![signal])
+    /// Deliver all queued signals and wait for the result
+    ///
+    /// Returns once every signal queued before the call, including batches already
+    /// being posted in the background, has been delivered or has failed.
+    /// If several batches fail, the first error is returned. Signals dropped because the
+    /// queue was full since the previous call are reported as
+    /// [`TelemetryDeckError::QueueOverflow`], even if they were dropped before a background
+    /// flush.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// let client = TelemetryDeck::new("YOUR-APP-ID");
+    ///
+    /// client.send("checkoutStarted", None, None, None, None);
+    /// client.send("checkoutCompleted", None, None, None, None);
+    ///
+    /// // Deliver right away, e.g. before the page is hidden
+    /// client.flush().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn flush(&self) -> Result<(), TelemetryDeckError> {
+        let url = self.build_url();
+        let policy = self.config.retry_policy;
+        let result = self
+            .queue
+            .flush(|signals| send_many(&*self.config.transport, &url, signals, policy))
+            .await;
+        self.queue.take_overflow().and(result)
+    }
+
+    pub(crate) fn send_one(&self, signal: Signal) {
+        match self.queue.push(signal) {
+            QueueAction::FlushNow => self.spawn_flush(None),
+            QueueAction::ScheduleFlush => {
+                self.spawn_flush(Some(self.queue.config().flush_interval));
+            }
+            QueueAction::None => {}
+        }
     }
 
-    fn send_many(&self, signals: Vec<Signal>) {
+    fn spawn_flush(&self, delay: Option<Duration>) {
+        let queue = Arc::clone(&self.queue);
         let url = self.build_url();
+        let policy = self.config.retry_policy;
+        let transport = Arc::clone(&self.config.transport);
         spawn_local(async move {
-            let body = serde_json::to_string(&signals).unwrap();
-            let _resp = Request::post(&url)
-                .body(body)
-                .header("Content-Type", "application/json")
-                .send()
+            if let Some(delay) = delay {
+                sleep(delay).await;
+            }
+            let _ = queue
+                .flush(|signals| send_many(&*transport, &url, signals, policy))
                 .await;
         });
     }
 
-    async fn send_many_sync(&self, signals: Vec<Signal>) -> Result<(), Box<dyn std::error::Error>> {
-        let url = self.build_url();
-        let body = serde_json::to_string(&signals)?;
-        let resp = Request::post(&url)
-            .body(body)
-            .header("Content-Type", "application/json")
-            .send()
-            .await?;
-
-        if resp.ok() {
-            Ok(())
-        } else {
-            Err(format!("HTTP error: {}", resp.status()).into())
-        }
+    pub(crate) async fn send_many_sync(
+        &self,
+        signals: Vec<Signal>,
+    ) -> Result<(), TelemetryDeckError> {
+        send_many(
+            &*self.config.transport,
+            &self.build_url(),
+            signals,
+            self.config.retry_policy,
+        )
+        .await
+    }
+}
+
+async fn send_many(
+    transport: &dyn Transport,
+    url: &str,
+    signals: Vec<Signal>,
+    policy: RetryPolicy,
+) -> Result<(), TelemetryDeckError> {
+    let body = serde_json::to_string(&signals)?;
+    with_retries(policy, || transport.post(url, body.clone()), sleep).await
+}
+
+pub(crate) fn default_transport(
+    http: &HttpConfig,
+) -> Result<Arc<dyn Transport>, TelemetryDeckError> {
+    Ok(Arc::new(ReqwasmTransport {
+        timeout: http.timeout,
+        user_agent: http.user_agent.clone(),
+        headers: Vec::new(),
+    }))
+}
+
+/// [`Transport`] posting signals with `reqwasm`, the default with the `wasm` feature
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{ReqwasmTransport, TelemetryDeck};
+/// use std::time::Duration;
+///
+/// let transport = ReqwasmTransport::new()
+///     .timeout(Duration::from_secs(5))
+///     .header("Authorization", "Bearer proxy-token");
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_transport(transport);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ReqwasmTransport {
+    timeout: Option<Duration>,
+    user_agent: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl ReqwasmTransport {
+    /// Transport without timeout or additional headers
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Give up on requests taking longer than `timeout`
+    #[must_use]
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// `User-Agent` header sent with every request
+    ///
+    /// Browsers may ignore it. By default no `User-Agent` header is set.
+    #[must_use]
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Additional header sent with every request
+    #[must_use]
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl Transport for ReqwasmTransport {
+    fn post<'a>(&'a self, url: &'a str, body: String) -> TransportFuture<'a> {
+        Box::pin(post(url, body, self))
+    }
+}
+
+async fn post(url: &str, body: String, http: &ReqwasmTransport) -> Result<(), TelemetryDeckError> {
+    let mut request = Request::post(url)
+        .body(body)
+        .header("Content-Type", "application/json");
+    if let Some(user_agent) = &http.user_agent {
+        request = request.header("User-Agent", user_agent);
+    }
+    for (name, value) in &http.headers {
+        request = request.header(name, value);
+    }
+    let response = request.send();
+    let resp = match http.timeout {
+        Some(timeout) => match select(Box::pin(response), Box::pin(sleep(timeout))).await {
+            Either::Left((resp, _)) => resp,
+            Either::Right(_) => {
+                return Err(TelemetryDeckError::Transport(format!(
+                    "request timed out after {:?}",
+                    timeout
+                )));
+            }
+        },
+        None => response.await,
+    }
+    .map_err(|e| TelemetryDeckError::Transport(e.to_string()))?;
+
+    if resp.ok() {
+        Ok(())
+    } else {
+        let retry_after = resp
+            .headers()
+            .get("Retry-After")
+            .ok()
+            .flatten()
+            .and_then(|value| parse_retry_after(&value, Utc::now()));
+        Err(TelemetryDeckError::HttpStatus {
+            status: resp.status(),
+            body: resp.text().await.unwrap_or_default(),
+            retry_after,
+        })
     }
 }
@@ -0,0 +1,159 @@
+//! Source of the current time for outgoing signals
+
+use chrono::{DateTime, Utc};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+#[cfg(not(feature = "wasm"))]
+use std::{sync::OnceLock, time::Instant};
+
+/// Provides the `receivedAt` timestamp of new signals
+///
+/// The client uses [`SystemClock`] unless another clock is configured with
+/// [`TelemetryDeck::with_clock`](crate::TelemetryDeck::with_clock). Use [`FixedClock`]
+/// in tests to get reproducible signals.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// The current time
+    fn now(&self) -> DateTime<Utc>;
+
+    /// Time elapsed since an arbitrary fixed point, used to measure durations
+    ///
+    /// Unlike [`now`](Self::now), it must never go backwards. Defaults to `now`, which is
+    /// fine for clocks that do not jump, such as [`FixedClock`].
+    fn monotonic(&self) -> Duration {
+        (self.now() - DateTime::UNIX_EPOCH)
+            .to_std()
+            .unwrap_or_default()
+    }
+}
+
+impl<T: Clock + ?Sized> Clock for Arc<T> {
+    fn now(&self) -> DateTime<Utc> {
+        (**self).now()
+    }
+
+    fn monotonic(&self) -> Duration {
+        (**self).monotonic()
+    }
+}
+
+/// [`Clock`] reading the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+
+    /// Time since the first call, from [`std::time::Instant`]
+    #[cfg(not(feature = "wasm"))]
+    fn monotonic(&self) -> Duration {
+        static START: OnceLock<Instant> = OnceLock::new();
+        START.get_or_init(Instant::now).elapsed()
+    }
+
+    /// Time since the page was loaded, from `performance.now()`
+    ///
+    /// Falls back to the system time where `window.performance` is not available.
+    #[cfg(feature = "wasm")]
+    fn monotonic(&self) -> Duration {
+        match web_sys::window().and_then(|window| window.performance()) {
+            Some(performance) => Duration::from_secs_f64(performance.now().max(0.0) / 1000.0),
+            None => (Utc::now() - DateTime::UNIX_EPOCH)
+                .to_std()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// [`Clock`] that only moves when told to
+///
+/// Clones share the same time, so a test can keep a clone to advance the clock
+/// used by a client.
+///
+/// # Examples
+///
+/// ```
+/// use chrono::{TimeZone, Utc};
+/// use std::time::Duration;
+/// use telemetrydeck_wasm::{FixedClock, TelemetryDeck};
+///
+/// let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_clock(clock.clone());
+///
+/// clock.advance(Duration::from_secs(60));
+/// let signal = client.signal("tick").build();
+/// assert_eq!(signal.received_at.to_rfc3339(), "2025-01-15T10:31:00+00:00");
+/// ```
+#[derive(Debug, Clone)]
+pub struct FixedClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl FixedClock {
+    /// Clock stopped at `now`
+    #[must_use]
+    pub fn new(now: DateTime<Utc>) -> Self {
+        FixedClock {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Move the clock to `now`
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.lock() = now;
+    }
+
+    /// Move the clock forward by `duration`
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.lock();
+        *now += chrono::Duration::from_std(duration).unwrap_or(chrono::Duration::MAX);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, DateTime<Utc>> {
+        self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Clock, FixedClock, SystemClock};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    #[test]
+    fn fixed_clock_moves_only_when_told() {
+        let start = Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap();
+        let sut = FixedClock::new(start);
+        assert_eq!(sut.now(), start);
+
+        let shared = sut.clone();
+        shared.advance(Duration::from_millis(1500));
+        assert_eq!(
+            sut.now(),
+            Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 1).unwrap()
+                + chrono::Duration::milliseconds(500)
+        );
+
+        sut.set(start);
+        assert_eq!(shared.now(), start);
+    }
+
+    #[test]
+    fn monotonic_time_follows_the_clock() {
+        let sut = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let start = sut.monotonic();
+        sut.advance(Duration::from_millis(1500));
+        assert_eq!(sut.monotonic() - start, Duration::from_millis(1500));
+
+        let start = SystemClock.monotonic();
+        assert!(SystemClock.monotonic() >= start);
+    }
+}
@@ -1,8 +1,29 @@
+use crate::acquisition::PendingInstall;
+use crate::builder::DEFAULT_URL;
+use crate::calendar::{CalendarTimeZone, calendar_params};
+#[cfg(not(feature = "wasm"))]
+use crate::client_native::default_transport;
+#[cfg(feature = "wasm")]
+use crate::client_wasm::default_transport;
+use crate::clock::{Clock, SystemClock};
+use crate::ids::{IdGenerator, RandomIdGenerator};
+use crate::navigation::CurrentPath;
+use crate::purchase::ExchangeRates;
+use crate::queue::{BatchConfig, SignalQueue};
+use crate::retention::RetentionTracker;
+use crate::retry::RetryPolicy;
+use crate::session::{SessionActivity, SessionConfig};
+#[cfg(not(feature = "wasm"))]
+use crate::spool::Spool;
+use crate::timer::Stopwatch;
+use crate::transport::{HttpConfig, Transport};
+use crate::{params, signals};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use std::collections::HashMap;
-use uuid::Uuid;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
 const CLIENT_VERSION_KEY: &str = "telemetryClientVersion";
@@ -36,7 +57,7 @@ const CLIENT_VERSION_KEY: &str = "telemetryClientVersion";
 /// - `session_id` is a UUID v4 generated per client instance
 /// - `is_test_mode` is serialized as a string ("true" or "false")
 /// - `float_value` is omitted from JSON when `None`
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct Signal {
     /// Timestamp when this signal was generated (UTC)
@@ -63,9 +84,10 @@ pub struct Signal {
     #[serde(rename = "type")]
     pub signal_type: String,
 
-    /// Custom parameters encoded as "key:value" strings
+    /// Custom parameters encoded as "key:value" strings, sorted by key
     ///
-    /// Created from the HashMap passed to `send()` or `send_sync()`.
+    /// Created from the client's default parameters and the HashMap passed to `send()` or
+    /// `send_sync()`, the latter taking precedence for identical keys.
     /// Keys containing colons are sanitized (`:` → `_`).
     pub payload: Vec<String>,
 
@@ -83,6 +105,29 @@ pub struct Signal {
     pub float_value: Option<f64>,
 }
 
+impl Signal {
+    /// Value of the payload parameter `key`, if present
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// let client = TelemetryDeck::new("YOUR-APP-ID");
+    /// let signal = client.signal("exportFinished").param("format", "pdf").build();
+    /// assert_eq!(signal.param("format"), Some("pdf"));
+    /// ```
+    #[must_use]
+    pub fn param(&self, key: &str) -> Option<&str> {
+        self.payload.iter().find_map(|entry| {
+            entry
+                .split_once(':')
+                .filter(|(k, _)| *k == key)
+                .map(|(_, value)| value)
+        })
+    }
+}
+
 /// TelemetryDeck API client
 ///
 /// This is the main entry point for sending telemetry signals to TelemetryDeck.
@@ -113,6 +158,21 @@ pub struct Signal {
 /// );
 /// ```
 ///
+/// ## With the Builder
+///
+/// ```no_run
+/// use telemetrydeck_wasm::TelemetryDeck;
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+/// let client = TelemetryDeck::builder("YOUR-APP-ID")
+///     .url("https://telemetry-proxy.example.com")
+///     .timeout(Duration::from_secs(5))
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+///
 /// # Platform Support
 ///
 /// - **Native**: Uses `reqwest` + `tokio::spawn`
@@ -123,45 +183,147 @@ pub struct Signal {
 /// - User identifiers are always SHA-256 hashed
 /// - Optional salt is concatenated after user ID before hashing
 /// - Session IDs are random UUIDs
-#[derive(Debug)]
+///
+/// # Sharing
+///
+/// The client is cheap to clone: clones share the HTTP transport, the queue, the
+/// configuration and the session state. Session id, default parameters and user can be
+/// changed through `&self`, so one client can be shared by many tasks, for example as
+/// web server state.
+///
+/// ```no_run
+/// use telemetrydeck_wasm::TelemetryDeck;
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+/// let handler_client = client.clone();
+///
+/// handler_client.reset_session(None);
+/// assert_eq!(client.session_id(), handler_client.session_id());
+/// ```
+#[derive(Debug, Clone)]
 pub struct TelemetryDeck {
+    /// Settings fixed when the client is created
+    pub(crate) config: Arc<ClientConfig>,
+
+    /// Signals waiting to be delivered by the next flush
+    pub(crate) queue: Arc<SignalQueue>,
+
+    /// Session id, default parameters and user, shared by all clones
+    pub(crate) state: Arc<RwLock<SessionState>>,
+}
+
+/// Settings of a [`TelemetryDeck`] client
+#[derive(Debug, Clone)]
+pub(crate) struct ClientConfig {
     /// Base URL of the TelemetryDeck service
-    ///
-    /// Default: `https://nom.telemetrydeck.com`
-    url: String,
+    pub(crate) url: String,
 
-    /// Your TelemetryDeck App ID
-    pub app_id: String,
+    /// TelemetryDeck App ID
+    pub(crate) app_id: String,
 
-    /// Optional namespace for multi-tenant deployments
-    ///
-    /// When set, signals are sent to `/v2/namespace/{namespace}/`
-    /// instead of `/v2/`.
-    pub namespace: Option<String>,
+    /// Namespace for multi-tenant deployments
+    pub(crate) namespace: Option<String>,
 
-    /// Optional salt for user identifier hashing
-    ///
-    /// The salt is concatenated after the user identifier before
-    /// SHA-256 hashing: `hash(user_id + salt)`.
-    ///
-    /// # Security Note
-    ///
-    /// It is recommended to use a cryptographically random salt of at least
-    /// 64 characters. The salt should be unique per application but consistent
-    /// across all users of the same application.
-    pub salt: Option<String>,
+    /// Salt for user identifier hashing
+    pub(crate) salt: Option<String>,
 
-    /// Default parameters appended to all outgoing signals
-    ///
-    /// These are merged with per-signal parameters.
-    /// The library version is automatically added as `telemetryClientVersion`.
-    pub default_params: HashMap<String, String>,
+    /// How failed deliveries are retried
+    pub(crate) retry_policy: RetryPolicy,
 
-    /// Current session identifier (UUID v4)
-    ///
-    /// Generated automatically when the client is created.
-    /// Can be reset using [`TelemetryDeck::reset_session`].
-    pub session_id: String,
+    /// Test mode used when a signal does not specify one
+    pub(crate) is_test_mode: bool,
+
+    /// Posts batches of signals to the ingestion service
+    pub(crate) transport: Arc<dyn Transport>,
+
+    /// Timestamps new signals
+    pub(crate) clock: Arc<dyn Clock>,
+
+    /// Creates session identifiers
+    pub(crate) ids: Arc<dyn IdGenerator>,
+
+    /// Where undelivered fire-and-forget signals are kept, if configured
+    #[cfg(not(feature = "wasm"))]
+    pub(crate) spool: Option<Arc<Spool>>,
+
+    /// Automatic session tracking, if enabled
+    pub(crate) sessions: Option<SessionConfig>,
+
+    /// Records tracked sessions to compute retention parameters, if configured
+    pub(crate) retention: Option<Arc<RetentionTracker>>,
+
+    /// Time zone of calendar parameters, if they are added
+    pub(crate) calendar: Option<CalendarTimeZone>,
+
+    /// Rates converting purchases to US dollars, the bundled ones if not configured
+    pub(crate) exchange_rates: Option<Arc<ExchangeRates>>,
+}
+
+/// Mutable state of a [`TelemetryDeck`] client
+#[derive(Debug)]
+pub(crate) struct SessionState {
+    /// Current session identifier
+    pub(crate) session_id: String,
+
+    /// Parameters appended to all outgoing signals
+    pub(crate) default_params: HashMap<String, String>,
+
+    /// Parameters added by live [`DefaultParamsGuard`](crate::DefaultParamsGuard)s, oldest first
+    pub(crate) scoped_params: Vec<(u64, HashMap<String, String>)>,
+
+    /// Identifier of the next scoped override
+    pub(crate) next_scope: u64,
+
+    /// User of signals that do not specify one
+    pub(crate) user: Option<String>,
+
+    /// Activity of the current session, when session tracking is enabled
+    pub(crate) activity: Option<SessionActivity>,
+
+    /// Duration of the previous tracked session
+    pub(crate) previous_session_seconds: Option<u64>,
+
+    /// Parameters computed by the client, such as retention metrics and the first
+    /// session date
+    pub(crate) derived_params: HashMap<String, String>,
+
+    /// New install to announce with `TelemetryDeck.Acquisition.newInstallDetected`
+    pub(crate) pending_install: Option<PendingInstall>,
+
+    /// Path recorded by the last [`Navigator::navigate`](crate::Navigator::navigate)
+    pub(crate) navigation: Option<CurrentPath>,
+
+    /// Named timers by signal type
+    pub(crate) timers: HashMap<String, Stopwatch>,
+}
+
+impl SessionState {
+    /// Default parameters with the session parameters and scoped overrides applied,
+    /// newest scope last
+    pub(crate) fn effective_default_params(&self) -> Cow<'_, HashMap<String, String>> {
+        if self.scoped_params.is_empty()
+            && self.previous_session_seconds.is_none()
+            && self.derived_params.is_empty()
+        {
+            return Cow::Borrowed(&self.default_params);
+        }
+        let mut params = self.default_params.clone();
+        if let Some(seconds) = self.previous_session_seconds {
+            params.insert(
+                params::retention::PREVIOUS_SESSION_SECONDS.to_string(),
+                seconds.to_string(),
+            );
+        }
+        params.extend(
+            self.derived_params
+                .iter()
+                .map(|(k, v)| (k.clone(), v.clone())),
+        );
+        for (_, scoped) in &self.scoped_params {
+            params.extend(scoped.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+        Cow::Owned(params)
+    }
 }
 
 impl TelemetryDeck {
@@ -212,28 +374,308 @@ impl TelemetryDeck {
         salt: Option<String>,
         params: HashMap<String, String>,
     ) -> Self {
-        TelemetryDeck {
-            url: String::from("https://nom.telemetrydeck.com"),
+        let config = ClientConfig {
+            url: DEFAULT_URL.to_string(),
             app_id: app_id.to_string(),
             namespace,
             salt,
-            default_params: Self::adding_params(
-                &params,
-                Some(HashMap::from([(
-                    CLIENT_VERSION_KEY.to_string(),
-                    VERSION.to_string(),
-                )])),
-            ),
-            session_id: Uuid::new_v4().to_string(),
+            retry_policy: RetryPolicy::default(),
+            is_test_mode: false,
+            transport: default_transport(&HttpConfig::default())
+                .expect("failed to create the default HTTP client"),
+            clock: Arc::new(SystemClock),
+            ids: Arc::new(RandomIdGenerator),
+            #[cfg(not(feature = "wasm"))]
+            spool: None,
+            sessions: None,
+            retention: None,
+            calendar: None,
+            exchange_rates: None,
+        };
+        Self::from_config(config, BatchConfig::default(), params)
+    }
+
+    /// Create a new instance from complete settings, with a new session
+    pub(crate) fn from_config(
+        config: ClientConfig,
+        batch_config: BatchConfig,
+        params: HashMap<String, String>,
+    ) -> Self {
+        TelemetryDeck {
+            state: Arc::new(RwLock::new(SessionState {
+                session_id: config.ids.session_id(),
+                default_params: Self::with_client_version(params),
+                scoped_params: Vec::new(),
+                next_scope: 0,
+                user: None,
+                activity: None,
+                previous_session_seconds: None,
+                derived_params: HashMap::new(),
+                pending_install: None,
+                navigation: None,
+                timers: HashMap::new(),
+            })),
+            config: Arc::new(config),
+            queue: Arc::new(SignalQueue::new(batch_config)),
         }
     }
 
+    /// Replace the batching configuration used by [`send`](Self::send)
+    ///
+    /// Signals already queued by this instance are discarded, so this should be
+    /// called right after the client is created.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use telemetrydeck_wasm::{BatchConfig, TelemetryDeck};
+    /// use std::time::Duration;
+    ///
+    /// // Post every signal on its own, as soon as it is sent
+    /// let client = TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+    ///     .with_batch_config(BatchConfig {
+    ///         max_batch_size: 1,
+    ///         flush_interval: Duration::ZERO,
+    ///         ..BatchConfig::default()
+    ///     });
+    /// ```
+    #[must_use]
+    pub fn with_batch_config(mut self, config: BatchConfig) -> Self {
+        self.queue = Arc::new(SignalQueue::new(config));
+        self
+    }
+
+    /// Replace the retry policy used for all deliveries
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use telemetrydeck_wasm::{RetryPolicy, TelemetryDeck};
+    ///
+    /// // Give up after the first failed attempt
+    /// let client = TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+    ///     .with_retry_policy(RetryPolicy::none());
+    /// ```
+    #[must_use]
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.config_mut().retry_policy = policy;
+        self
+    }
+
+    /// Replace the transport used to post signals
+    ///
+    /// See [`Transport`] for what implementations are expected to do.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # #[cfg(not(feature = "wasm"))]
+    /// # fn main() {
+    /// use telemetrydeck_wasm::{ReqwestTransport, TelemetryDeck};
+    ///
+    /// let http = reqwest::Client::builder()
+    ///     .proxy(reqwest::Proxy::https("http://egress.internal:3128").unwrap())
+    ///     .build()
+    ///     .unwrap();
+    /// let client = TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+    ///     .with_transport(ReqwestTransport::with_client(http));
+    /// # }
+    /// # #[cfg(feature = "wasm")]
+    /// # fn main() {}
+    /// ```
+    #[must_use]
+    pub fn with_transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.config_mut().transport = Arc::new(transport);
+        self
+    }
+
+    /// Replace the clock timestamping new signals
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use chrono::{TimeZone, Utc};
+    /// use telemetrydeck_wasm::{FixedClock, TelemetryDeck};
+    ///
+    /// let now = Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap();
+    /// let client = TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+    ///     .with_clock(FixedClock::new(now));
+    /// assert_eq!(client.signal("tick").build().received_at, now);
+    /// ```
+    #[must_use]
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.config_mut().clock = Arc::new(clock);
+        self
+    }
+
+    /// Start and renew sessions automatically
+    ///
+    /// See [`SessionConfig`].
+    #[must_use]
+    pub fn with_session_tracking(mut self, config: SessionConfig) -> Self {
+        self.config_mut().sessions = Some(config);
+        self
+    }
+
+    /// Add calendar parameters computed in `time_zone` to every signal
+    ///
+    /// All of [`params::calendar`] are derived from the signal's `received_at`. Default
+    /// and per-signal parameters with the same keys take precedence.
+    #[must_use]
+    pub fn with_calendar_params(mut self, time_zone: CalendarTimeZone) -> Self {
+        self.config_mut().calendar = Some(time_zone);
+        self
+    }
+
+    /// Convert purchase prices to US dollars with `rates` instead of the bundled ones
+    ///
+    /// See [`ExchangeRates`].
+    #[must_use]
+    pub fn with_exchange_rates(mut self, rates: ExchangeRates) -> Self {
+        self.config_mut().exchange_rates = Some(Arc::new(rates));
+        self
+    }
+
+    /// Replace the generator of session identifiers
+    ///
+    /// The current session id is replaced by one from the new generator.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use telemetrydeck_wasm::{SeededIdGenerator, TelemetryDeck};
+    ///
+    /// let first = TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+    ///     .with_id_generator(SeededIdGenerator::new(42));
+    /// let second = TelemetryDeck::new("XXXXXXXX-XXXX-XXXX-XXXX-XXXXXXXXXXXX")
+    ///     .with_id_generator(SeededIdGenerator::new(42));
+    /// assert_eq!(first.session_id(), second.session_id());
+    /// ```
+    #[must_use]
+    pub fn with_id_generator(mut self, ids: impl IdGenerator + 'static) -> Self {
+        let ids: Arc<dyn IdGenerator> = Arc::new(ids);
+        self.write_state().session_id = ids.session_id();
+        self.config_mut().ids = ids;
+        self
+    }
+
+    /// Your TelemetryDeck App ID
+    #[must_use]
+    pub fn app_id(&self) -> &str {
+        &self.config.app_id
+    }
+
+    /// Namespace for multi-tenant deployments
+    ///
+    /// When set, signals are sent to `/v2/namespace/{namespace}/`
+    /// instead of `/v2/`.
+    #[must_use]
+    pub fn namespace(&self) -> Option<&str> {
+        self.config.namespace.as_deref()
+    }
+
+    /// Salt for user identifier hashing
+    ///
+    /// The salt is concatenated after the user identifier before
+    /// SHA-256 hashing: `hash(user_id + salt)`.
+    ///
+    /// # Security Note
+    ///
+    /// It is recommended to use a cryptographically random salt of at least
+    /// 64 characters. The salt should be unique per application but consistent
+    /// across all users of the same application.
+    #[must_use]
+    pub fn salt(&self) -> Option<&str> {
+        self.config.salt.as_deref()
+    }
+
+    /// Current session identifier (UUID v4 by default)
+    ///
+    /// Generated automatically when the client is created.
+    /// Can be reset using [`TelemetryDeck::reset_session`].
+    #[must_use]
+    pub fn session_id(&self) -> String {
+        self.read_state().session_id.clone()
+    }
+
     /// Reset the session id for future signals
-    pub fn reset_session(&mut self, new_session_id: Option<String>) {
-        self.session_id = new_session_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    ///
+    /// Without an explicit id, a new one is created by the client's
+    /// [`IdGenerator`]. The change applies to every clone of this client. With session
+    /// tracking, this ends the current session; the next signal starts a new one.
+    pub fn reset_session(&self, new_session_id: Option<String>) {
+        let session_id = new_session_id.unwrap_or_else(|| self.config.ids.session_id());
+        let mut state = self.write_state();
+        state.end_session();
+        state.session_id = session_id;
+    }
+
+    /// Default parameters appended to all outgoing signals
+    ///
+    /// These are merged with per-signal parameters; when both define the same key, the
+    /// per-signal value is sent. The library version is automatically added as
+    /// `telemetryClientVersion`. Parameters of live
+    /// [`DefaultParamsGuard`](crate::DefaultParamsGuard)s are included.
+    #[must_use]
+    pub fn default_params(&self) -> HashMap<String, String> {
+        self.read_state().effective_default_params().into_owned()
+    }
+
+    /// Replace the default parameters for future signals
+    ///
+    /// `telemetryClientVersion` is kept. The change applies to every clone of this client.
+    pub fn set_default_params(&self, params: HashMap<String, String>) {
+        self.write_state().default_params = Self::with_client_version(params);
+    }
+
+    /// User of signals that do not specify one
+    #[must_use]
+    pub fn user(&self) -> Option<String> {
+        self.read_state().user.clone()
+    }
+
+    /// Set the user of future signals that do not specify one
+    ///
+    /// Like every user identifier, it is SHA-256 hashed before it is sent.
+    /// The change applies to every clone of this client.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// let client = TelemetryDeck::new("YOUR-APP-ID");
+    /// client.set_user("user@example.com");
+    /// client.send("userLogin", None, None, None, None);
+    ///
+    /// client.clear_user();
+    /// client.send("userLogout", None, None, None, None);
+    /// ```
+    pub fn set_user(&self, user: impl Into<String>) {
+        self.write_state().user = Some(user.into());
+    }
+
+    /// Send future signals without a user unless they specify one
+    pub fn clear_user(&self) {
+        self.write_state().user = None;
+    }
+
+    /// Mutable settings, detached from clones created before
+    fn config_mut(&mut self) -> &mut ClientConfig {
+        Arc::make_mut(&mut self.config)
+    }
+
+    pub(crate) fn read_state(&self) -> RwLockReadGuard<'_, SessionState> {
+        self.state.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn write_state(&self) -> RwLockWriteGuard<'_, SessionState> {
+        self.state.write().unwrap_or_else(|e| e.into_inner())
     }
 
     /// Create a signal with the specified parameters
+    ///
+    /// Has no side effects: the session is not touched and nothing is queued.
     pub(crate) fn create_signal(
         &self,
         signal_type: &str,
@@ -242,13 +684,106 @@ impl TelemetryDeck {
         is_test_mode: Option<bool>,
         float_value: Option<f64>,
     ) -> Signal {
-        let params = Self::adding_params(&self.default_params, payload);
-        let payload = Self::encoded_payload(params);
+        self.signal_at(
+            self.config.clock.now(),
+            signal_type,
+            client_user,
+            payload,
+            is_test_mode,
+            float_value,
+        )
+    }
 
-        let client_user = client_user.map_or_else(
+    /// Create a signal that is about to be sent
+    ///
+    /// Records the session activity and queues the announcements it triggers, such as
+    /// [`signals::session::STARTED`], before creating the signal like
+    /// [`create_signal`](Self::create_signal). Announcements have the user and test mode
+    /// of the signal that triggered them.
+    pub(crate) fn create_outgoing_signal(
+        &self,
+        signal_type: &str,
+        client_user: Option<&str>,
+        payload: Option<HashMap<String, String>>,
+        is_test_mode: Option<bool>,
+        float_value: Option<f64>,
+    ) -> Signal {
+        let now = self.config.clock.now();
+        let (announcements, new_install) = self.record_send(now);
+        for announcement in announcements {
+            let signal = self.signal_at(now, announcement, client_user, None, is_test_mode, None);
+            self.send_one(signal);
+        }
+        if let Some(install) = new_install {
+            install.remember();
+        }
+        if let Some(retention) = &self.config.retention {
+            retention.persist(now);
+        }
+        self.signal_at(
+            now,
+            signal_type,
+            client_user,
+            payload,
+            is_test_mode,
+            float_value,
+        )
+    }
+
+    /// Update the session for a signal sent at `now`
+    ///
+    /// Returns the signals to announce and the new install to store once they are
+    /// queued.
+    fn record_send(&self, now: DateTime<Utc>) -> (Vec<&'static str>, Option<PendingInstall>) {
+        let mut announcements = Vec::new();
+        if self.config.sessions.is_none() && self.read_state().pending_install.is_none() {
+            return (announcements, None);
+        }
+        let mut state = self.write_state();
+        let new_install = state.pending_install.take();
+        if new_install.is_some() {
+            announcements.push(signals::acquisition::NEW_INSTALL_DETECTED);
+        }
+        if let Some(sessions) = &self.config.sessions {
+            let started = state.record_activity(now, sessions.idle_timeout, &*self.config.ids);
+            if let Some(retention) = &self.config.retention {
+                if started {
+                    let params = retention.session_started(now, state.previous_session_seconds);
+                    state.derived_params.extend(params);
+                } else if let Some(activity) = &state.activity {
+                    retention.session_continued(now, activity.duration().as_secs());
+                }
+            }
+            if started {
+                announcements.push(signals::session::STARTED);
+            }
+        }
+        (announcements, new_install)
+    }
+
+    fn signal_at(
+        &self,
+        received_at: DateTime<Utc>,
+        signal_type: &str,
+        client_user: Option<&str>,
+        payload: Option<HashMap<String, String>>,
+        is_test_mode: Option<bool>,
+        float_value: Option<f64>,
+    ) -> Signal {
+        let state = self.read_state();
+        let mut defaults = state.effective_default_params();
+        if let Some(time_zone) = self.config.calendar {
+            let defaults = defaults.to_mut();
+            for (key, value) in calendar_params(received_at, time_zone) {
+                defaults.entry(key.to_string()).or_insert(value);
+            }
+        }
+        let payload = Self::encoded_payload(&defaults, payload.unwrap_or_default());
+
+        let client_user = client_user.or(state.user.as_deref()).map_or_else(
             || "rust".to_string(),
             |u| {
-                let user_with_salt = if let Some(salt) = &self.salt {
+                let user_with_salt = if let Some(salt) = &self.config.salt {
                     format!("{}{}", u, salt)
                 } else {
                     u.to_string()
@@ -259,45 +794,56 @@ impl TelemetryDeck {
             },
         );
         Signal {
-            received_at: Utc::now(),
-            app_id: self.app_id.clone(),
+            received_at,
+            app_id: self.config.app_id.clone(),
             client_user,
-            session_id: self.session_id.clone(),
+            session_id: state.session_id.clone(),
             signal_type: signal_type.to_string(),
             payload,
-            is_test_mode: is_test_mode.unwrap_or(false).to_string(),
+            is_test_mode: is_test_mode.unwrap_or(self.config.is_test_mode).to_string(),
             float_value,
         }
     }
 
     /// Build the API URL for sending signals
     pub(crate) fn build_url(&self) -> String {
-        if let Some(namespace) = &self.namespace {
-            format!("{}/v2/namespace/{}/", self.url, namespace)
+        if let Some(namespace) = &self.config.namespace {
+            format!("{}/v2/namespace/{}/", self.config.url, namespace)
         } else {
-            format!("{}/v2/", self.url)
+            format!("{}/v2/", self.config.url)
         }
     }
 
-    fn adding_params(
-        params1: &HashMap<String, String>,
-        params2: Option<HashMap<String, String>>,
-    ) -> HashMap<String, String> {
-        let mut result = params1.clone();
-        if let Some(params) = params2 {
-            result.extend(params);
-        }
-        result
+    fn with_client_version(mut params: HashMap<String, String>) -> HashMap<String, String> {
+        params.insert(CLIENT_VERSION_KEY.to_string(), VERSION.to_string());
+        params
     }
 
-    /// Encode parameters as "key:value" strings
+    /// Encode parameters as "key:value" strings, sorted by key
     ///
     /// Colons in parameter keys are replaced with underscores to avoid
-    /// conflicts with the "key:value" encoding format.
-    fn encoded_payload(params: HashMap<String, String>) -> Vec<String> {
-        params
+    /// conflicts with the "key:value" encoding format. Per-signal parameters
+    /// override default parameters with the same (sanitized) key. If several keys
+    /// of the same map sanitize to the same key, the one sorting last wins.
+    fn encoded_payload(
+        defaults: &HashMap<String, String>,
+        params: HashMap<String, String>,
+    ) -> Vec<String> {
+        let defaults: BTreeMap<&str, &str> = defaults
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let params: BTreeMap<&str, &str> = params
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .collect();
+        let mut merged = BTreeMap::new();
+        for (key, value) in defaults.into_iter().chain(params) {
+            merged.insert(key.replace(':', "_"), value);
+        }
+        merged
             .into_iter()
-            .map(|(k, v)| format!("{}:{}", k.replace(':', "_"), v))
+            .map(|(k, v)| format!("{}:{}", k, v))
             .collect()
     }
 }
@@ -399,18 +945,136 @@ mod tests {
 
     #[test]
     fn reset_session() {
-        let mut sut = TelemetryDeck::new("1234");
-        let session1 = sut.session_id.clone();
+        let sut = TelemetryDeck::new("1234");
+        let session1 = sut.session_id();
         sut.reset_session(None);
-        let session2 = sut.session_id.clone();
+        let session2 = sut.session_id();
         assert_ne!(session1, session2);
     }
 
     #[test]
     fn reset_session_with_specific_id() {
-        let mut sut = TelemetryDeck::new("1234");
+        let sut = TelemetryDeck::new("1234");
         sut.reset_session(Some("my session".to_string()));
-        let session2 = sut.session_id.clone();
+        let session2 = sut.session_id();
         assert_eq!(session2, "my session".to_string());
     }
+
+    #[test]
+    fn deterministic_client_produces_exact_json() {
+        use crate::{FixedClock, SeededIdGenerator};
+        use chrono::{TimeZone, Utc};
+
+        let sut = TelemetryDeck::builder("1234")
+            .clock(FixedClock::new(
+                Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap(),
+            ))
+            .id_generator(SeededIdGenerator::new(42))
+            .build()
+            .unwrap();
+        let json =
+            serde_json::to_string(&sut.create_signal("signal_type", None, None, None, Some(1.5)))
+                .unwrap();
+        let again = TelemetryDeck::new("1234")
+            .with_clock(FixedClock::new(
+                Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap(),
+            ))
+            .with_id_generator(SeededIdGenerator::new(42));
+        assert_eq!(
+            serde_json::to_string(&again.create_signal("signal_type", None, None, None, Some(1.5)))
+                .unwrap(),
+            json
+        );
+        assert_eq!(
+            json,
+            format!(
+                r#"{{"receivedAt":"2025-01-15T10:30:00Z","appID":"1234","clientUser":"rust","sessionID":"ca71d87c-7698-4989-be5b-a61552085fc6","type":"signal_type","payload":["telemetryClientVersion:{VERSION}"],"isTestMode":"false","floatValue":1.5}}"#
+            )
+        );
+    }
+
+    #[test]
+    fn reset_session_uses_id_generator() {
+        use crate::FixedIdGenerator;
+
+        let sut =
+            TelemetryDeck::new("1234").with_id_generator(FixedIdGenerator("fixed".to_string()));
+        assert_eq!(sut.session_id(), "fixed");
+        sut.reset_session(Some("other".to_string()));
+        sut.reset_session(None);
+        assert_eq!(sut.session_id(), "fixed");
+    }
+
+    #[test]
+    fn payload_is_sorted_and_signal_params_override_defaults() {
+        let sut = TelemetryDeck::new_with_config(
+            "1234",
+            None,
+            None,
+            HashMap::from([
+                ("screen".to_string(), "home".to_string()),
+                ("app:build".to_string(), "42".to_string()),
+            ]),
+        );
+        let params = HashMap::from([
+            ("screen".to_string(), "settings".to_string()),
+            ("action".to_string(), "toggle".to_string()),
+            ("app_build".to_string(), "43".to_string()),
+        ]);
+        for _ in 0..10 {
+            let result = sut.create_signal("signal_type", None, Some(params.clone()), None, None);
+            assert_eq!(
+                result.payload,
+                vec![
+                    "action:toggle".to_string(),
+                    "app_build:43".to_string(),
+                    "screen:settings".to_string(),
+                    format!("telemetryClientVersion:{VERSION}"),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn clones_share_session_state() {
+        let sut = TelemetryDeck::new("1234");
+        let clone = sut.clone();
+
+        clone.reset_session(Some("shared".to_string()));
+        clone.set_default_params(HashMap::from([("plan".to_string(), "pro".to_string())]));
+        clone.set_user("clientUser");
+
+        let result = sut.create_signal("signal_type", None, None, None, None);
+        assert_eq!(result.session_id, "shared");
+        assert_eq!(
+            result.payload,
+            vec![
+                "plan:pro".to_string(),
+                format!("telemetryClientVersion:{VERSION}")
+            ]
+        );
+        assert_eq!(
+            result.client_user,
+            "6721870580401922549fe8fdb09a064dba5b8792fa018d3bd9ffa90fe37a0149"
+        );
+
+        sut.clear_user();
+        let result = clone.create_signal("signal_type", None, None, None, None);
+        assert_eq!(result.client_user, "rust");
+    }
+
+    #[tokio::test]
+    async fn client_can_be_shared_across_tasks() {
+        let sut = TelemetryDeck::new("1234");
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let client = sut.clone();
+                tokio::spawn(async move { client.reset_session(Some(format!("session {i}"))) })
+            })
+            .collect();
+        for handle in handles {
+            handle.await.unwrap();
+        }
+        assert!(sut.session_id().starts_with("session "));
+    }
 }
@@ -0,0 +1,170 @@
+//! Changing default parameters while the client is in use
+//!
+//! Defaults often become known after startup (plan tier, locale, feature-flag cohort).
+//! They can be set and removed one at a time, or added for as long as a
+//! [`DefaultParamsGuard`] lives. Every change applies to all clones of the client.
+
+use crate::core::{SessionState, TelemetryDeck};
+use crate::payload::PayloadValue;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+/// Default parameters added to outgoing signals until the guard is dropped
+///
+/// Created with [`TelemetryDeck::scoped_default_params`]. The parameters apply to every
+/// signal created through the client or its clones while the guard is alive, and
+/// override regular default parameters with the same key. When guards overlap, the
+/// newest one wins. Per-signal parameters still take precedence over all defaults.
+///
+/// The guard does not borrow the client, so it can be moved into a spawned task to cover
+/// the lifetime of that task.
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::TelemetryDeck;
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+///
+/// {
+///     let _checkout = client.scoped_default_params([("flow", "checkout")]);
+///     client.send("paymentStarted", None, None, None, None); // includes flow:checkout
+/// }
+///
+/// client.send("paymentFinished", None, None, None, None); // no flow parameter
+/// ```
+#[derive(Debug)]
+#[must_use = "the parameters are removed as soon as the guard is dropped"]
+pub struct DefaultParamsGuard {
+    state: Arc<RwLock<SessionState>>,
+    scope: u64,
+}
+
+impl TelemetryDeck {
+    /// Add or replace a default parameter for future signals
+    ///
+    /// The value is encoded with [`PayloadValue::encode`]. The change applies to every
+    /// clone of this client.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// let client = TelemetryDeck::new("YOUR-APP-ID");
+    /// client.set_default_param("plan", "pro");
+    /// client.set_default_param("betaCohort", 3);
+    /// client.send("appStarted", None, None, None, None);
+    ///
+    /// client.remove_default_param("betaCohort");
+    /// ```
+    pub fn set_default_param(&self, key: impl Into<String>, value: impl Into<PayloadValue>) {
+        let value = value.into().encode();
+        self.write_state().default_params.insert(key.into(), value);
+    }
+
+    /// Remove a default parameter for future signals, returning its encoded value
+    ///
+    /// Parameters added by a [`DefaultParamsGuard`] are not affected.
+    pub fn remove_default_param(&self, key: &str) -> Option<String> {
+        self.write_state().default_params.remove(key)
+    }
+
+    /// Add default parameters until the returned guard is dropped
+    ///
+    /// See [`DefaultParamsGuard`].
+    pub fn scoped_default_params<I, K, V>(&self, params: I) -> DefaultParamsGuard
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<PayloadValue>,
+    {
+        let params: HashMap<String, String> = params
+            .into_iter()
+            .map(|(key, value)| (key.into(), value.into().encode()))
+            .collect();
+        let mut state = self.write_state();
+        let scope = state.next_scope;
+        state.next_scope += 1;
+        state.scoped_params.push((scope, params));
+        DefaultParamsGuard {
+            state: Arc::clone(&self.state),
+            scope,
+        }
+    }
+}
+
+impl Drop for DefaultParamsGuard {
+    fn drop(&mut self) {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+        state
+            .scoped_params
+            .retain(|(scope, _)| *scope != self.scope);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TelemetryDeck;
+
+    fn payload(client: &TelemetryDeck) -> Vec<String> {
+        client
+            .create_signal("signal_type", None, None, None, None)
+            .payload
+            .into_iter()
+            .filter(|param| !param.starts_with("telemetryClientVersion:"))
+            .collect()
+    }
+
+    #[test]
+    fn set_and_remove_default_param() {
+        let sut = TelemetryDeck::new("1234");
+        let clone = sut.clone();
+        sut.set_default_param("plan", "pro");
+        sut.set_default_param("cohort", 3);
+        assert_eq!(payload(&clone), vec!["cohort:3", "plan:pro"]);
+
+        assert_eq!(clone.remove_default_param("cohort"), Some("3".to_string()));
+        assert_eq!(sut.remove_default_param("cohort"), None);
+        assert_eq!(payload(&sut), vec!["plan:pro"]);
+    }
+
+    #[test]
+    fn scoped_params_apply_until_guard_is_dropped() {
+        let sut = TelemetryDeck::new("1234");
+        sut.set_default_param("screen", "home");
+
+        let outer = sut.scoped_default_params([("screen", "checkout"), ("flow", "cart")]);
+        let inner = sut.scoped_default_params([("screen", "payment")]);
+        assert_eq!(payload(&sut), vec!["flow:cart", "screen:payment"]);
+        assert_eq!(sut.default_params()["screen"], "payment");
+
+        drop(outer);
+        assert_eq!(payload(&sut), vec!["screen:payment"]);
+
+        drop(inner);
+        assert_eq!(payload(&sut), vec!["screen:home"]);
+    }
+
+    #[test]
+    fn signal_params_override_scoped_params() {
+        let sut = TelemetryDeck::new("1234");
+        let _guard = sut.scoped_default_params([("screen", "checkout")]);
+        let signal = sut.signal("signal_type").param("screen", "cart").build();
+        assert_eq!(signal.param("screen"), Some("cart"));
+    }
+
+    #[tokio::test]
+    async fn guard_can_move_into_a_task() {
+        let sut = TelemetryDeck::new("1234");
+        let guard = sut.scoped_default_params([("job", "export")]);
+        let client = sut.clone();
+        tokio::spawn(async move {
+            let _guard = guard;
+            assert_eq!(payload(&client), vec!["job:export"]);
+        })
+        .await
+        .unwrap();
+        assert!(payload(&sut).is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+//! Device and operating system parameters detected on native targets
+//!
+//! On Linux, the distribution and its version come from `/etc/os-release`, falling back
+//! to the kernel release reported by `uname`. Architecture and platform come from
+//! [`std::env::consts`], the time zone from `TZ`, `/etc/timezone` or `/etc/localtime`,
+//! and the locale from `LC_ALL`, `LC_MESSAGES` or `LANG`. Parameters that cannot be
+//! detected are left out.
+
+use crate::core::TelemetryDeck;
+use crate::params;
+use std::collections::HashMap;
+use std::env::{self, consts};
+use std::fs;
+
+/// File describing the Linux distribution
+const OS_RELEASE_PATH: &str = "/etc/os-release";
+
+/// Kernel release, as printed by `uname -r`
+const KERNEL_RELEASE_PATH: &str = "/proc/sys/kernel/osrelease";
+
+impl TelemetryDeck {
+    /// Add the detected device and operating system parameters to the default parameters
+    /// (native only)
+    ///
+    /// Sets [`params::device`] `OPERATING_SYSTEM`, `PLATFORM`, `ARCHITECTURE`,
+    /// `SYSTEM_VERSION`, `SYSTEM_MAJOR_VERSION`, `SYSTEM_MAJOR_MINOR_VERSION` and
+    /// `TIME_ZONE`, and [`params::run_context::LOCALE`], when they can be detected.
+    /// Default parameters that are already set are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// let client = TelemetryDeck::new("YOUR-APP-ID").with_device_params();
+    /// ```
+    #[must_use]
+    pub fn with_device_params(self) -> Self {
+        let detected = device_params();
+        let mut state = self.write_state();
+        for (key, value) in detected {
+            state.default_params.entry(key).or_insert(value);
+        }
+        drop(state);
+        self
+    }
+}
+
+/// Detect the device and operating system parameters of the running system
+pub(crate) fn device_params() -> HashMap<String, String> {
+    let os_release = fs::read_to_string(OS_RELEASE_PATH)
+        .ok()
+        .map(|contents| parse_os_release(&contents))
+        .unwrap_or_default();
+    let platform = platform_name(consts::OS);
+
+    let mut result = HashMap::from([
+        (
+            params::device::OPERATING_SYSTEM.to_string(),
+            os_release
+                .get("NAME")
+                .cloned()
+                .unwrap_or_else(|| platform.clone()),
+        ),
+        (params::device::PLATFORM.to_string(), platform),
+        (
+            params::device::ARCHITECTURE.to_string(),
+            consts::ARCH.to_string(),
+        ),
+    ]);
+
+    let version = os_release.get("VERSION_ID").cloned().or_else(|| {
+        fs::read_to_string(KERNEL_RELEASE_PATH)
+            .ok()
+            .map(|release| release.trim().to_string())
+    });
+    if let Some(version) = version.filter(|version| !version.is_empty()) {
+        result.extend(version_params(&version));
+    }
+
+    let localtime = fs::read_link("/etc/localtime")
+        .ok()
+        .map(|target| target.to_string_lossy().into_owned());
+    if let Some(time_zone) = time_zone(
+        env::var("TZ").ok(),
+        fs::read_to_string("/etc/timezone").ok(),
+        localtime,
+    ) {
+        result.insert(params::device::TIME_ZONE.to_string(), time_zone);
+    }
+
+    let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .into_iter()
+        .find_map(|name| env::var(name).ok().and_then(|value| locale(&value)));
+    if let Some(locale) = locale {
+        result.insert(params::run_context::LOCALE.to_string(), locale);
+    }
+    result
+}
+
+/// Display name of a [`consts::OS`] value
+fn platform_name(os: &str) -> String {
+    match os {
+        "linux" => "Linux",
+        "macos" => "macOS",
+        "windows" => "Windows",
+        "freebsd" => "FreeBSD",
+        "android" => "Android",
+        "ios" => "iOS",
+        other => other,
+    }
+    .to_string()
+}
+
+/// Fields of an `os-release` file, with quotes removed
+fn parse_os_release(contents: &str) -> HashMap<String, String> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim();
+            let value = value
+                .strip_prefix('"')
+                .and_then(|v| v.strip_suffix('"'))
+                .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                .unwrap_or(value);
+            (key.trim().to_string(), value.to_string())
+        })
+        .collect()
+}
+
+/// Full, major and `major.minor` system versions
+///
+/// Only the leading digits of each component are kept, so `6.8.0-45-generic` has the
+/// major version `6`. A missing minor version is `0`.
+fn version_params(version: &str) -> HashMap<String, String> {
+    let mut components = version.split('.').map(|component| {
+        let digits: String = component.chars().take_while(char::is_ascii_digit).collect();
+        (!digits.is_empty()).then_some(digits)
+    });
+    let mut result = HashMap::from([(
+        params::device::SYSTEM_VERSION.to_string(),
+        version.to_string(),
+    )]);
+    if let Some(Some(major)) = components.next() {
+        let minor = components
+            .next()
+            .flatten()
+            .unwrap_or_else(|| "0".to_string());
+        result.insert(
+            params::device::SYSTEM_MAJOR_MINOR_VERSION.to_string(),
+            format!("{major}.{minor}"),
+        );
+        result.insert(params::device::SYSTEM_MAJOR_VERSION.to_string(), major);
+    }
+    result
+}
+
+/// IANA time zone from the `TZ` variable, `/etc/timezone` or the `/etc/localtime` link
+fn time_zone(
+    tz: Option<String>,
+    etc_timezone: Option<String>,
+    localtime_target: Option<String>,
+) -> Option<String> {
+    let from_link = localtime_target.and_then(|target| {
+        target
+            .split_once("zoneinfo/")
+            .map(|(_, name)| name.to_string())
+    });
+    [
+        tz.map(|tz| tz.trim_start_matches(':').to_string()),
+        etc_timezone,
+        from_link,
+    ]
+    .into_iter()
+    .flatten()
+    .map(|name| name.trim().to_string())
+    .find(|name| !name.is_empty())
+}
+
+/// BCP 47 style locale (`de-BE`) from a POSIX locale (`de_BE.UTF-8@euro`)
+///
+/// `C` and `POSIX` are not locales of a user and are ignored.
+fn locale(value: &str) -> Option<String> {
+    let name = value.split(['.', '@']).next().unwrap_or_default().trim();
+    if name.is_empty() || name == "C" || name == "POSIX" {
+        return None;
+    }
+    Some(name.replace('_', "-"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{locale, parse_os_release, platform_name, time_zone, version_params};
+    use crate::{TelemetryDeck, params};
+
+    #[test]
+    fn parses_os_release() {
+        let sut = parse_os_release(
+            "# comment\nNAME=\"Ubuntu\"\nVERSION_ID=\"22.04\"\nID=ubuntu\nPRETTY_NAME='Ubuntu 22.04.4 LTS'\n",
+        );
+        assert_eq!(sut["NAME"], "Ubuntu");
+        assert_eq!(sut["VERSION_ID"], "22.04");
+        assert_eq!(sut["ID"], "ubuntu");
+        assert_eq!(sut["PRETTY_NAME"], "Ubuntu 22.04.4 LTS");
+    }
+
+    #[test]
+    fn splits_versions() {
+        let sut = version_params("6.8.0-45-generic");
+        assert_eq!(sut[params::device::SYSTEM_VERSION], "6.8.0-45-generic");
+        assert_eq!(sut[params::device::SYSTEM_MAJOR_VERSION], "6");
+        assert_eq!(sut[params::device::SYSTEM_MAJOR_MINOR_VERSION], "6.8");
+
+        let sut = version_params("12");
+        assert_eq!(sut[params::device::SYSTEM_MAJOR_MINOR_VERSION], "12.0");
+
+        let sut = version_params("rolling");
+        assert_eq!(sut.len(), 1);
+    }
+
+    #[test]
+    fn detects_time_zone_in_order() {
+        let link = Some("/usr/share/zoneinfo/Europe/Brussels".to_string());
+        assert_eq!(
+            time_zone(Some(":America/New_York".to_string()), None, link.clone()),
+            Some("America/New_York".to_string())
+        );
+        assert_eq!(
+            time_zone(None, Some("Asia/Tokyo\n".to_string()), link.clone()),
+            Some("Asia/Tokyo".to_string())
+        );
+        assert_eq!(
+            time_zone(None, None, link),
+            Some("Europe/Brussels".to_string())
+        );
+        assert_eq!(time_zone(None, None, None), None);
+    }
+
+    #[test]
+    fn converts_posix_locales() {
+        assert_eq!(locale("de_BE.UTF-8@euro"), Some("de-BE".to_string()));
+        assert_eq!(locale("en"), Some("en".to_string()));
+        assert_eq!(locale("C.UTF-8"), None);
+        assert_eq!(locale("POSIX"), None);
+        assert_eq!(locale(""), None);
+    }
+
+    #[test]
+    fn explicit_defaults_are_kept() {
+        assert_eq!(platform_name("macos"), "macOS");
+        let sut = TelemetryDeck::builder("1234")
+            .default_param(params::device::PLATFORM, "server")
+            .build()
+            .unwrap()
+            .with_device_params();
+        let defaults = sut.default_params();
+        assert_eq!(defaults[params::device::PLATFORM], "server");
+        assert_eq!(
+            defaults[params::device::ARCHITECTURE],
+            std::env::consts::ARCH
+        );
+    }
+}
@@ -0,0 +1,171 @@
+//! Error type returned by the client
+
+use std::fmt;
+use std::time::Duration;
+
+/// Errors returned by [`TelemetryDeck`](crate::TelemetryDeck)
+///
+/// The same type is used on native and WebAssembly targets. It is `Send + Sync`, so it
+/// can be returned from tasks spawned with `tokio::spawn`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{TelemetryDeck, TelemetryDeckError};
+///
+/// # async fn example() {
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+///
+/// match client.send_sync("criticalEvent", None, None, None, None).await {
+///     Ok(()) => {}
+///     Err(TelemetryDeckError::HttpStatus { status, body, .. }) => {
+///         eprintln!("Rejected with {status}: {body}");
+///     }
+///     Err(e) => eprintln!("Failed to send: {e}"),
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum TelemetryDeckError {
+    /// Signals could not be serialized to JSON
+    Serialization(serde_json::Error),
+
+    /// The request could not be completed (connection refused, DNS failure, timeout...)
+    Transport(String),
+
+    /// The server answered with a non-success status code
+    HttpStatus {
+        /// HTTP status code of the response
+        status: u16,
+        /// Body of the response, empty if it could not be read
+        body: String,
+        /// Delay requested by the server through the `Retry-After` header
+        retry_after: Option<Duration>,
+    },
+
+    /// The client configuration is not valid
+    InvalidConfiguration(String),
+
+    /// Signals were dropped because the queue was full
+    QueueOverflow {
+        /// Number of signals dropped since the previous flush
+        dropped: usize,
+    },
+
+    /// Reading or writing local storage failed (for example the offline spool)
+    Io(std::io::Error),
+
+    /// A purchase or exchange rate is not valid (unknown currency code, negative price...)
+    InvalidPurchase(String),
+}
+
+impl TelemetryDeckError {
+    /// Whether the failed delivery is worth retrying
+    ///
+    /// Transport errors and `408`, `429` and `5xx` responses are retryable; everything
+    /// else is a permanent failure.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            TelemetryDeckError::Transport(_) => true,
+            TelemetryDeckError::HttpStatus { status, .. } => {
+                *status == 408 || *status == 429 || (500..600).contains(status)
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn retry_after(&self) -> Option<Duration> {
+        match self {
+            TelemetryDeckError::HttpStatus { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for TelemetryDeckError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TelemetryDeckError::Serialization(e) => write!(f, "serialization error: {}", e),
+            TelemetryDeckError::Transport(message) => write!(f, "transport error: {}", message),
+            TelemetryDeckError::HttpStatus { status, body, .. } if body.is_empty() => {
+                write!(f, "HTTP error: {}", status)
+            }
+            TelemetryDeckError::HttpStatus { status, body, .. } => {
+                write!(f, "HTTP error: {}: {}", status, body)
+            }
+            TelemetryDeckError::InvalidConfiguration(message) => {
+                write!(f, "invalid configuration: {}", message)
+            }
+            TelemetryDeckError::QueueOverflow { dropped } => {
+                write!(f, "queue overflow: {} signals dropped", dropped)
+            }
+            TelemetryDeckError::Io(e) => write!(f, "I/O error: {}", e),
+            TelemetryDeckError::InvalidPurchase(message) => {
+                write!(f, "invalid purchase: {}", message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TelemetryDeckError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TelemetryDeckError::Serialization(e) => Some(e),
+            TelemetryDeckError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for TelemetryDeckError {
+    fn from(e: serde_json::Error) -> Self {
+        TelemetryDeckError::Serialization(e)
+    }
+}
+
+impl From<std::io::Error> for TelemetryDeckError {
+    fn from(e: std::io::Error) -> Self {
+        TelemetryDeckError::Io(e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TelemetryDeckError;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn error_is_send_and_sync() {
+        assert_send_sync::<TelemetryDeckError>();
+    }
+
+    #[test]
+    fn retryable_variants() {
+        let status = |status| TelemetryDeckError::HttpStatus {
+            status,
+            body: String::new(),
+            retry_after: None,
+        };
+        assert!(TelemetryDeckError::Transport("refused".to_string()).is_retryable());
+        for code in [408, 429, 500, 502, 503, 599] {
+            assert!(status(code).is_retryable(), "{code}");
+        }
+        for code in [400, 401, 403, 404, 413, 422] {
+            assert!(!status(code).is_retryable(), "{code}");
+        }
+        assert!(!TelemetryDeckError::QueueOverflow { dropped: 1 }.is_retryable());
+    }
+
+    #[test]
+    fn display_includes_status_and_body() {
+        let sut = TelemetryDeckError::HttpStatus {
+            status: 400,
+            body: "invalid appID".to_string(),
+            retry_after: None,
+        };
+        assert_eq!(sut.to_string(), "HTTP error: 400: invalid appID");
+    }
+}
@@ -0,0 +1,224 @@
+//! Optional process-wide client with free tracking functions
+//!
+//! Large applications can install one [`TelemetryDeck`] with [`init`] and send signals
+//! from anywhere with [`signal`], [`signal_with`], [`track`](track()) or the
+//! [`track!`](crate::track!) macro, instead of passing a client through every layer.
+//!
+//! Signals sent before [`init`] are buffered, up to [`MAX_PENDING_SIGNALS`], and
+//! delivered by the client once it is installed. They keep the time at which they were
+//! sent, read from the system clock: no client, and so no configured
+//! [`Clock`], exists yet.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::TelemetryDeck;
+//!
+//! # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! // Buffered until the client is installed
+//! telemetrydeck_wasm::signal("appLaunched");
+//!
+//! telemetrydeck_wasm::init(TelemetryDeck::builder("YOUR-APP-ID").build()?)?;
+//!
+//! telemetrydeck_wasm::signal_with("settingsOpened", [("section", "privacy")]);
+//! telemetrydeck_wasm::track!("exportFinished", "format" => "pdf", "pages" => 12);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::clock::{Clock, SystemClock};
+use crate::core::TelemetryDeck;
+use crate::error::TelemetryDeckError;
+use crate::payload::PayloadValue;
+use crate::track::TelemetrySignal;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+/// Maximum number of signals kept before [`init`]; later ones are dropped
+pub const MAX_PENDING_SIGNALS: usize = 100;
+
+static CLIENT: OnceLock<TelemetryDeck> = OnceLock::new();
+
+static PENDING: Mutex<Vec<PendingSignal>> = Mutex::new(Vec::new());
+
+/// Signal sent before the global client was installed
+#[derive(Debug)]
+struct PendingSignal {
+    signal_type: String,
+    params: HashMap<String, String>,
+    float_value: Option<f64>,
+    received_at: DateTime<Utc>,
+}
+
+/// Install the global client and deliver the signals buffered so far
+///
+/// Buffered signals are sent like [`TelemetryDeck::send`], so on native targets this
+/// must be called from within a tokio runtime when signals were sent before. They keep
+/// the system time at which they were sent, even if `client` has another
+/// [`Clock`].
+///
+/// # Errors
+///
+/// Returns [`TelemetryDeckError::InvalidConfiguration`] if a global client is already
+/// installed; it is not replaced.
+pub fn init(client: TelemetryDeck) -> Result<(), TelemetryDeckError> {
+    let mut pending = lock_pending();
+    CLIENT.set(client).map_err(|_| {
+        TelemetryDeckError::InvalidConfiguration(
+            "the global client is already initialized".to_string(),
+        )
+    })?;
+    let client = CLIENT.get().expect("the global client was just installed");
+    for buffered in pending.drain(..) {
+        let mut signal = client.create_outgoing_signal(
+            &buffered.signal_type,
+            None,
+            Some(buffered.params),
+            None,
+            buffered.float_value,
+        );
+        signal.received_at = buffered.received_at;
+        client.send_one(signal);
+    }
+    Ok(())
+}
+
+/// The global client, if [`init`] was called
+#[must_use]
+pub fn client() -> Option<&'static TelemetryDeck> {
+    CLIENT.get()
+}
+
+/// Send a signal through the global client (fire-and-forget)
+///
+/// Buffered if the client is not installed yet.
+pub fn signal(signal_type: &str) {
+    dispatch(signal_type, HashMap::new(), None);
+}
+
+/// Send a signal with payload parameters through the global client (fire-and-forget)
+///
+/// Values are encoded with [`PayloadValue::encode`]. Buffered if the client is not
+/// installed yet.
+pub fn signal_with<I, K, V>(signal_type: &str, params: I)
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: Into<String>,
+    V: Into<PayloadValue>,
+{
+    let params = params
+        .into_iter()
+        .map(|(key, value)| (key.into(), value.into().encode()))
+        .collect();
+    dispatch(signal_type, params, None);
+}
+
+/// Send a typed signal through the global client (fire-and-forget)
+///
+/// Buffered if the client is not installed yet.
+pub fn track<T: TelemetrySignal>(event: T) {
+    dispatch(T::SIGNAL_TYPE, event.payload(), event.float_value());
+}
+
+/// Deliver every signal queued by the global client
+///
+/// Does nothing if the client is not installed yet.
+pub async fn flush() -> Result<(), TelemetryDeckError> {
+    match CLIENT.get() {
+        Some(client) => client.flush().await,
+        None => Ok(()),
+    }
+}
+
+fn dispatch(signal_type: &str, params: HashMap<String, String>, float_value: Option<f64>) {
+    if let Some(client) = CLIENT.get() {
+        client.send(signal_type, None, Some(params), None, float_value);
+        return;
+    }
+    let mut pending = lock_pending();
+    // `init` may have run while waiting for the lock
+    if let Some(client) = CLIENT.get() {
+        drop(pending);
+        client.send(signal_type, None, Some(params), None, float_value);
+    } else if pending.len() < MAX_PENDING_SIGNALS {
+        pending.push(PendingSignal {
+            signal_type: signal_type.to_string(),
+            params,
+            float_value,
+            // No client clock exists before `init`, so buffered signals use wall-clock time
+            received_at: SystemClock.now(),
+        });
+    }
+}
+
+fn lock_pending() -> std::sync::MutexGuard<'static, Vec<PendingSignal>> {
+    PENDING.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Send a signal through the global client, with optional `key => value` parameters
+///
+/// Values can be of any type convertible to [`PayloadValue`]. Expands to
+/// [`signal`](crate::signal) or [`signal_with`](crate::signal_with), so signals are
+/// buffered until [`init`](crate::init) is called.
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{params, track};
+///
+/// track!("appLaunched");
+/// track!("exportFinished", "format" => "pdf", "pages" => 12);
+/// track!("pageViewed", params::navigation::DESTINATION_PATH => "/settings");
+/// ```
+#[macro_export]
+macro_rules! track {
+    ($signal_type:expr $(,)?) => {
+        $crate::signal($signal_type)
+    };
+    ($signal_type:expr, $($key:expr => $value:expr),+ $(,)?) => {
+        $crate::signal_with(
+            $signal_type,
+            [$((
+                ::std::string::String::from($key),
+                $crate::PayloadValue::from($value),
+            )),+],
+        )
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MAX_PENDING_SIGNALS;
+    use crate::TelemetryDeck;
+
+    // The global client can only be installed once per process, so the whole lifecycle
+    // is covered by a single test.
+    #[tokio::test]
+    async fn buffers_until_init_then_sends_through_global_client() {
+        assert!(crate::client().is_none());
+        crate::flush().await.unwrap();
+        crate::signal("appLaunched");
+        crate::track!("settingsOpened", "section" => "privacy", "visits" => 2);
+        for _ in 0..MAX_PENDING_SIGNALS {
+            crate::signal("overflow");
+        }
+
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        crate::init(client).unwrap();
+        crate::flush().await.unwrap();
+        recorder.assert_signal_sent("appLaunched");
+        recorder
+            .assert_signal_sent_with("settingsOpened", &[("section", "privacy"), ("visits", "2")]);
+        assert_eq!(recorder.signals().len(), MAX_PENDING_SIGNALS);
+
+        recorder.clear();
+        crate::track!("exportFinished");
+        crate::flush().await.unwrap();
+        recorder.assert_signal_sent("exportFinished");
+
+        let (other, _) = TelemetryDeck::new_recording("5678");
+        assert!(crate::init(other).is_err());
+        assert_eq!(crate::client().unwrap().app_id(), "1234");
+    }
+}
@@ -0,0 +1,99 @@
+//! Generation of session identifiers
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Creates session identifiers
+///
+/// The client uses [`RandomIdGenerator`] unless another generator is configured with
+/// [`TelemetryDeck::with_id_generator`](crate::TelemetryDeck::with_id_generator). Use
+/// [`SeededIdGenerator`] or [`FixedIdGenerator`] in tests to get reproducible signals.
+pub trait IdGenerator: fmt::Debug + Send + Sync {
+    /// A new session identifier
+    fn session_id(&self) -> String;
+}
+
+impl<T: IdGenerator + ?Sized> IdGenerator for Arc<T> {
+    fn session_id(&self) -> String {
+        (**self).session_id()
+    }
+}
+
+/// [`IdGenerator`] creating random UUID v4 identifiers
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RandomIdGenerator;
+
+impl IdGenerator for RandomIdGenerator {
+    fn session_id(&self) -> String {
+        Uuid::new_v4().to_string()
+    }
+}
+
+/// [`IdGenerator`] creating UUID v4 identifiers from a seeded random number generator
+///
+/// Two generators with the same seed return the same sequence of identifiers, on every
+/// platform.
+///
+/// # Examples
+///
+/// ```
+/// use telemetrydeck_wasm::{IdGenerator, SeededIdGenerator};
+///
+/// let first = SeededIdGenerator::new(42);
+/// let second = SeededIdGenerator::new(42);
+/// assert_eq!(first.session_id(), second.session_id());
+/// ```
+#[derive(Debug)]
+pub struct SeededIdGenerator {
+    rng: Mutex<fastrand::Rng>,
+}
+
+impl SeededIdGenerator {
+    /// Generator starting from `seed`
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        SeededIdGenerator {
+            rng: Mutex::new(fastrand::Rng::with_seed(seed)),
+        }
+    }
+}
+
+impl IdGenerator for SeededIdGenerator {
+    fn session_id(&self) -> String {
+        let bits = self.rng.lock().unwrap_or_else(|e| e.into_inner()).u128(..);
+        uuid::Builder::from_bytes(bits.to_be_bytes())
+            .set_variant(uuid::Variant::RFC4122)
+            .set_version(uuid::Version::Random)
+            .build()
+            .to_string()
+    }
+}
+
+/// [`IdGenerator`] always returning the same identifier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixedIdGenerator(pub String);
+
+impl IdGenerator for FixedIdGenerator {
+    fn session_id(&self) -> String {
+        self.0.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IdGenerator, SeededIdGenerator};
+
+    #[test]
+    fn seeded_ids_are_reproducible_uuids() {
+        let sut = SeededIdGenerator::new(7);
+        let ids = [sut.session_id(), sut.session_id()];
+        assert_ne!(ids[0], ids[1]);
+
+        let again = SeededIdGenerator::new(7);
+        assert_eq!([again.session_id(), again.session_id()], ids);
+
+        let parsed = uuid::Uuid::parse_str(&ids[0]).unwrap();
+        assert_eq!(parsed.get_version_num(), 4);
+    }
+}
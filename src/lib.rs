@@ -10,8 +10,30 @@
 //! - **Privacy by Default**: Automatic SHA-256 hashing of user identifiers with optional salt
 //! - **Multi-tenant Support**: Optional namespace parameter for multi-tenant deployments
 //! - **Fire-and-Forget or Error Handling**: Choose between `send()` (async spawn) or `send_sync()` (returns Result)
+//! - **Fluent Signals**: Build signals with [`TelemetryDeck::signal`] instead of positional arguments
+//! - **Typed Parameters**: Numbers, booleans, dates and lists are encoded consistently via [`PayloadValue`]
+//! - **Serde Payloads**: Any `Serialize` struct can be turned into payload parameters
+//! - **Typed Signals**: `#[derive(TelemetrySignal)]` checks signal definitions at compile time (`derive` feature)
+//! - **Typed Errors**: Failures are reported as [`TelemetryDeckError`], which is `Send + Sync`
+//! - **Batching**: Fire-and-forget signals are queued and posted together, with an explicit `flush()`
+//! - **Retries**: Failed deliveries are retried with exponential backoff and jitter
+//! - **Pluggable Transport**: Swap the HTTP stack or add headers through the [`Transport`] trait
+//! - **Deterministic Mode**: Inject a [`Clock`] and an [`IdGenerator`] for reproducible signals
+//! - **Test Helpers**: Record signals in memory and assert on them (`testing` feature)
+//! - **Offline Spool**: Native clients can keep undelivered signals on disk and send them later
 //! - **Reserved Constants**: Pre-defined signal types and parameter names for common use cases
-//! - **Session Management**: Automatic session ID generation and management
+//! - **Session Management**: Automatic session ID generation, optional session tracking with an idle timeout
+//! - **Retention Metrics**: Session counts, days used and average durations from a pluggable [`KeyValueStore`]
+//! - **New Install Detection**: Announces the first run and attaches the first session date
+//! - **Navigation Tracking**: A [`Navigator`] reports path changes with the time spent on each path
+//! - **Duration Timers**: Measure durations with guards or named timers, with pause and resume
+//! - **Purchases**: Validated currency codes, price micros and US dollar values for revenue signals
+//! - **Calendar Parameters**: Day of week, hour of day, weekend... in the user's time zone
+//! - **Device Parameters**: Native clients can detect the OS, its version, architecture, time zone and locale
+//! - **Browser Parameters**: WebAssembly clients can add language, screen, time zone and media preferences
+//! - **Shared Client**: Cheap to clone; session, default parameters and user change through `&self`
+//! - **Runtime Default Parameters**: Set, remove or temporarily add parameters sent with every signal
+//! - **Global Client**: Optional process-wide client with free functions and a `track!` macro
 //! - **TelemetryDeck v2 API**: Full support for the latest API features
 //!
 //! # Installation
@@ -26,6 +48,13 @@
 //! tokio = { version = "1", features = ["rt", "macros"] }
 //! ```
 //!
+//! Enable the `derive` feature for `#[derive(TelemetrySignal)]`:
+//!
+//! ```toml
+//! [dependencies]
+//! telemetrydeck-wasm = { version = "0.4", features = ["derive"] }
+//! ```
+//!
 //! ## WebAssembly Applications
 //!
 //! Add this to your `Cargo.toml`:
@@ -45,6 +74,9 @@
 //!
 //! // Send a signal (fire-and-forget)
 //! client.send("userLogin", Some("user@example.com"), None, None, None);
+//!
+//! // Or describe it step by step
+//! client.signal("userLogin").user("user@example.com").param("method", "sso").send();
 //! ```
 //!
 //! # Examples
@@ -84,6 +116,137 @@
 //! client.send("revenue", Some("user123"), None, None, Some(99.99));
 //! ```
 //!
+//! ## Fluent Signal Builder
+//!
+//! [`TelemetryDeck::signal`] avoids passing `None` for every setting you don't need:
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::TelemetryDeck;
+//!
+//! # async fn example() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! let client = TelemetryDeck::new("YOUR-APP-ID");
+//!
+//! client
+//!     .signal("userLogin")
+//!     .user("user123")
+//!     .param("method", "sso")
+//!     .param("attempts", 2)
+//!     .param("rememberMe", true)
+//!     .float(1.5)
+//!     .send();
+//!
+//! // Wait for delivery and handle errors
+//! client.signal("criticalEvent").test_mode(true).send_and_wait().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Payloads from Serde Structs
+//!
+//! Events already described as `#[derive(Serialize)]` structs can be sent as they are.
+//! Nested structs become dotted keys, enums their variant names, and `None` is omitted.
+//! See [`payload::to_payload`] for the exact rules.
+//!
+//! ```no_run
+//! use serde::Serialize;
+//! use telemetrydeck_wasm::TelemetryDeck;
+//!
+//! #[derive(Serialize)]
+//! struct Checkout {
+//!     items: u32,
+//!     coupon: Option<String>,
+//! }
+//!
+//! # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! let client = TelemetryDeck::new("YOUR-APP-ID");
+//!
+//! client
+//!     .signal("checkout")
+//!     .params_from(&Checkout { items: 3, coupon: None })?
+//!     .send();
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Strongly Typed Signals
+//!
+//! With the `derive` feature, signal definitions are checked at compile time. The signal
+//! type, payload keys and float value are generated from attributes:
+//!
+//! ```no_run
+//! # #[cfg(feature = "derive")]
+//! # fn main() {
+//! use telemetrydeck_wasm::{TelemetryDeck, TelemetrySignal, params};
+//!
+//! #[derive(TelemetrySignal)]
+//! #[signal(type = "App.checkout")]
+//! struct Checkout {
+//!     #[signal(float_value)]
+//!     total: f64,
+//!     #[signal(param = "TelemetryDeck.Purchase.currencyCode")]
+//!     currency: String,
+//!     items: u32,
+//! }
+//!
+//! let client = TelemetryDeck::new("YOUR-APP-ID");
+//! client.track(Checkout { total: 42.0, currency: "EUR".to_string(), items: 3 });
+//! # }
+//! # #[cfg(not(feature = "derive"))]
+//! # fn main() {}
+//! ```
+//!
+//! ## Default Parameters at Runtime
+//!
+//! Parameters learned after startup can be added to every following signal. A
+//! [`DefaultParamsGuard`] adds parameters only while it is alive, for example for the
+//! duration of a scope or a spawned task.
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::TelemetryDeck;
+//!
+//! let client = TelemetryDeck::new("YOUR-APP-ID");
+//! client.set_default_param("plan", "pro");
+//! client.set_default_param("locale", "de-BE");
+//!
+//! let _flow = client.scoped_default_params([("flow", "onboarding")]);
+//! client.send("stepCompleted", None, None, None, None);
+//! ```
+//!
+//! ## Global Client
+//!
+//! Instead of passing a client around, install one with [`init`] and send signals from
+//! anywhere. Signals sent before [`init`] are buffered and delivered once it is called.
+//! See the [`global`] module.
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::{TelemetryDeck, track};
+//!
+//! # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! telemetrydeck_wasm::init(TelemetryDeck::new("YOUR-APP-ID"))?;
+//!
+//! telemetrydeck_wasm::signal("appLaunched");
+//! track!("exportFinished", "format" => "pdf", "pages" => 12);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Session Tracking
+//!
+//! With [`SessionConfig`], the client sends `TelemetryDeck.Session.started` before the
+//! first signal of a session and starts a new session after a period without signals.
+//! Signals of the following session carry the previous session's duration.
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::{SessionConfig, TelemetryDeck};
+//!
+//! # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! let client = TelemetryDeck::builder("YOUR-APP-ID")
+//!     .session_tracking(SessionConfig::default())
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Multi-tenant Deployment with Namespace
 //!
 //! ```no_run
@@ -100,6 +263,30 @@
 //! client.send("userAction", Some("user123"), None, None, None);
 //! ```
 //!
+//! ## Configuring the Client with a Builder
+//!
+//! [`TelemetryDeck::builder`] covers every setting, including a custom endpoint (for example
+//! a staging proxy), and validates the configuration when building.
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::TelemetryDeck;
+//! use std::time::Duration;
+//!
+//! # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! let client = TelemetryDeck::builder("YOUR-APP-ID")
+//!     .url("https://telemetry-proxy.staging.example.com")
+//!     .namespace("tenant-xyz")
+//!     .default_param("environment", "staging")
+//!     .test_mode(true)
+//!     .timeout(Duration::from_secs(10))
+//!     .user_agent("my-app/1.0")
+//!     .build()?;
+//!
+//! client.send("userAction", Some("user123"), None, None, None);
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Enhanced User Privacy with Salt
 //!
 //! ```no_run
@@ -153,18 +340,138 @@
 //! # }
 //! ```
 //!
+//! ## Batching and flush()
+//!
+//! Signals passed to `send()` are collected in an in-memory queue and posted as a single
+//! request once [`BatchConfig::max_batch_size`] signals are queued or
+//! [`BatchConfig::flush_interval`] has elapsed. Call `flush()` to deliver everything
+//! queued so far, for example before a short-lived program exits.
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::{BatchConfig, TelemetryDeck};
+//! use std::time::Duration;
+//!
+//! # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+//! let client = TelemetryDeck::new("YOUR-APP-ID").with_batch_config(BatchConfig {
+//!     max_batch_size: 100,
+//!     flush_interval: Duration::from_secs(10),
+//!     ..BatchConfig::default()
+//! });
+//!
+//! for _ in 0..250 {
+//!     client.send("itemProcessed", None, None, None, None);
+//! }
+//!
+//! client.flush().await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Retries
+//!
+//! Connection errors and `408`, `429` and `5xx` responses are retried according to the
+//! client's [`RetryPolicy`], honouring the `Retry-After` header when the server sends one.
+//! Other `4xx` responses are reported right away. The same policy applies to `send()`,
+//! `send_sync()` and `flush()`.
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::{RetryPolicy, TelemetryDeck};
+//! use std::time::Duration;
+//!
+//! let client = TelemetryDeck::new("YOUR-APP-ID").with_retry_policy(RetryPolicy {
+//!     max_attempts: 5,
+//!     base_delay: Duration::from_secs(1),
+//!     max_delay: Duration::from_secs(60),
+//!     jitter: 0.3,
+//! });
+//! ```
+//!
+//! ## Custom Transport
+//!
+//! Batches are posted through a [`Transport`]. The default is `ReqwestTransport` on
+//! native targets and `ReqwasmTransport` with the `wasm` feature; implement the trait to
+//! use another HTTP stack, add headers for a proxy, or intercept requests in tests.
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::{TelemetryDeck, Transport, TransportFuture};
+//!
+//! #[derive(Debug)]
+//! struct NullTransport;
+//!
+//! impl Transport for NullTransport {
+//!     fn post<'a>(&'a self, _url: &'a str, _body: String) -> TransportFuture<'a> {
+//!         Box::pin(async { Ok(()) })
+//!     }
+//! }
+//!
+//! let client = TelemetryDeck::new("YOUR-APP-ID").with_transport(NullTransport);
+//! ```
+//!
+//! ## Testing Code that Sends Signals
+//!
+//! With the `testing` feature, [`TelemetryDeck::new_recording`] creates a client that
+//! records signals in memory instead of sending them. See the `testing` module for the
+//! available assertions.
+//!
+//! ```toml
+//! [dev-dependencies]
+//! telemetrydeck-wasm = { version = "0.4", features = ["testing"] }
+//! ```
+//!
+//! ## Deterministic Signals
+//!
+//! Timestamps and session ids come from a [`Clock`] and an [`IdGenerator`]. With
+//! [`FixedClock`] and [`SeededIdGenerator`], the same code produces byte-for-byte
+//! identical JSON on every run and platform, which makes golden-file tests possible.
+//!
+//! ```
+//! use chrono::{TimeZone, Utc};
+//! use telemetrydeck_wasm::{FixedClock, SeededIdGenerator, TelemetryDeck};
+//!
+//! # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! let client = TelemetryDeck::builder("YOUR-APP-ID")
+//!     .clock(FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap()))
+//!     .id_generator(SeededIdGenerator::new(42))
+//!     .build()?;
+//! let json = serde_json::to_string(&client.signal("appStarted").build())?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Offline Spool (native only)
+//!
+//! Batches of fire-and-forget signals that still fail after all retries are lost by
+//! default. With a [`SpoolConfig`], those that failed with a retryable error are written
+//! to disk instead, and sent again after the next successful flush or when the next
+//! client using the same directory is built. Spooled signals keep their original
+//! timestamp; the spool is capped by size and age.
+//!
+//! ```no_run
+//! # #[cfg(not(feature = "wasm"))]
+//! # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! use telemetrydeck_wasm::{SpoolConfig, TelemetryDeck};
+//!
+//! let client = TelemetryDeck::builder("YOUR-APP-ID")
+//!     .spool(SpoolConfig::new("/var/lib/my-app/telemetry"))
+//!     .build()?;
+//! # Ok(())
+//! # }
+//! # #[cfg(feature = "wasm")]
+//! # fn main() {}
+//! ```
+//!
 //! # Platform-Specific Behavior
 //!
 //! ## Native Rust (default)
 //!
-//! - Uses `reqwest` for HTTP requests
-//! - Uses `tokio::spawn` for fire-and-forget signals
+//! - Uses `reqwest` for HTTP requests (`ReqwestTransport`)
+//! - Uses `tokio::spawn` and `tokio::time` to flush queued signals
 //! - Requires a tokio runtime to be running
 //!
 //! ## WebAssembly (with `wasm` feature)
 //!
-//! - Uses `reqwasm` for HTTP requests
-//! - Uses `wasm_bindgen_futures::spawn_local` for fire-and-forget signals
+//! - Uses `reqwasm` for HTTP requests (`ReqwasmTransport`)
+//! - Uses `wasm_bindgen_futures::spawn_local` and `gloo-timers` to flush queued signals
 //! - Works in browser event loop (no runtime needed)
 //!
 //! # Privacy and Security
@@ -182,9 +489,116 @@
 
 #![deny(missing_docs, missing_debug_implementations)]
 
+// Lets code generated by `#[derive(TelemetrySignal)]` refer to `::telemetrydeck_wasm`
+// from within this crate's own tests.
+extern crate self as telemetrydeck_wasm;
+
 mod core;
 pub use core::{Signal, TelemetryDeck};
 
+mod error;
+pub use error::TelemetryDeckError;
+
+mod builder;
+pub use builder::TelemetryDeckBuilder;
+
+mod signal_builder;
+pub use signal_builder::SignalBuilder;
+
+mod default_params;
+pub use default_params::DefaultParamsGuard;
+
+pub mod global;
+pub use global::{client, flush, init, signal, signal_with, track};
+
+/// Typed values for signal payload parameters
+///
+/// See [`PayloadValue`] for the canonical encodings.
+pub mod payload;
+pub use payload::PayloadValue;
+
+mod ser;
+
+mod track;
+pub use track::TelemetrySignal;
+
+/// Derive macro implementing [`TelemetrySignal`] (requires the `derive` feature)
+#[cfg(feature = "derive")]
+pub use telemetrydeck_wasm_derive::TelemetrySignal;
+
+#[doc(hidden)]
+pub mod __private {
+    //! Support code for `#[derive(TelemetrySignal)]`, not part of the public API
+
+    /// Conversion of the `#[signal(float_value)]` field
+    pub trait FloatValue {
+        fn float_value(&self) -> Option<f64>;
+    }
+
+    macro_rules! float_value {
+        ($($t:ty),*) => {
+            $(
+                impl FloatValue for $t {
+                    fn float_value(&self) -> Option<f64> {
+                        Some(f64::from(*self))
+                    }
+                }
+            )*
+        };
+    }
+
+    float_value!(f32, f64, i8, i16, i32, u8, u16, u32);
+
+    impl<T: FloatValue> FloatValue for Option<T> {
+        fn float_value(&self) -> Option<f64> {
+            self.as_ref().and_then(FloatValue::float_value)
+        }
+    }
+}
+
+mod queue;
+pub use queue::BatchConfig;
+
+mod retry;
+pub use retry::RetryPolicy;
+
+mod session;
+pub use session::SessionConfig;
+
+mod calendar;
+pub use calendar::CalendarTimeZone;
+
+mod navigation;
+pub use navigation::Navigator;
+
+mod timer;
+pub use timer::TimerGuard;
+
+mod purchase;
+pub use purchase::{ExchangeRates, Purchase, PurchaseType};
+
+mod browser;
+pub use browser::BrowserEnvironment;
+#[cfg(feature = "wasm")]
+pub use browser::WebBrowser;
+
+mod retention;
+
+mod acquisition;
+
+mod store;
+#[cfg(not(feature = "wasm"))]
+pub use store::FileStore;
+pub use store::{KeyValueStore, MemoryStore};
+
+#[cfg(not(feature = "wasm"))]
+mod device;
+
+#[cfg(not(feature = "wasm"))]
+mod spool;
+#[cfg(not(feature = "wasm"))]
+pub use spool::SpoolConfig;
+
 /// Reserved signal type constants defined by TelemetryDeck
 ///
 /// See the [signals] module documentation for usage examples.
@@ -195,8 +609,24 @@ pub mod signals;
 /// See the [params] module documentation for usage examples.
 pub mod params;
 
+mod transport;
+pub use transport::{Transport, TransportFuture};
+
+mod clock;
+pub use clock::{Clock, FixedClock, SystemClock};
+
+mod ids;
+pub use ids::{FixedIdGenerator, IdGenerator, RandomIdGenerator, SeededIdGenerator};
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+
 #[cfg(feature = "wasm")]
 mod client_wasm;
+#[cfg(feature = "wasm")]
+pub use client_wasm::ReqwasmTransport;
 
 #[cfg(not(feature = "wasm"))]
 mod client_native;
+#[cfg(not(feature = "wasm"))]
+pub use client_native::ReqwestTransport;
@@ -0,0 +1,178 @@
+//! Navigation tracking
+//!
+//! A [`Navigator`] remembers the path the user is on and sends
+//! [`signals::navigation::PATH_CHANGED`] each time it changes, with the time spent on
+//! the previous path.
+
+use crate::core::TelemetryDeck;
+use crate::{params, signals};
+use chrono::{DateTime, Utc};
+
+/// Version of the navigation parameters sent with [`signals::navigation::PATH_CHANGED`]
+const SCHEMA_VERSION: &str = "1";
+
+/// Path the user is on, shared by all navigators of a client
+#[derive(Debug, Clone)]
+pub(crate) struct CurrentPath {
+    pub(crate) path: String,
+    pub(crate) entered_at: DateTime<Utc>,
+}
+
+/// Sends a signal for every path change of the user
+///
+/// Created with [`TelemetryDeck::navigator`]. All navigators of a client and its clones
+/// share the current path, so a navigator can be created wherever the router lives.
+///
+/// Each [`navigate`](Self::navigate) sends [`signals::navigation::PATH_CHANGED`] with:
+///
+/// - the previous path as [`params::navigation::SOURCE_PATH`], empty for the first one
+/// - the new path as [`params::navigation::DESTINATION_PATH`]
+/// - `source -> destination` as [`params::navigation::IDENTIFIER`]
+/// - `1` as [`params::navigation::SCHEMA_VERSION`]
+/// - the seconds spent on the previous path, when there was one, as
+///   [`signals::signal::DURATION_IN_SECONDS`]
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::TelemetryDeck;
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+/// let navigator = client.navigator();
+///
+/// navigator.navigate("/home");
+/// navigator.navigate("/settings"); // "/home -> /settings"
+/// ```
+#[derive(Debug, Clone)]
+pub struct Navigator {
+    client: TelemetryDeck,
+}
+
+impl TelemetryDeck {
+    /// Navigator tracking the path changes of the user
+    ///
+    /// See [`Navigator`].
+    pub fn navigator(&self) -> Navigator {
+        Navigator {
+            client: self.clone(),
+        }
+    }
+}
+
+impl Navigator {
+    /// Record that the user moved from the current path to `destination`
+    pub fn navigate(&self, destination: &str) {
+        self.change_path(None, destination);
+    }
+
+    /// Record that the user moved from `source` to `destination`
+    ///
+    /// Use this when the previous path is known better than the navigator knows it,
+    /// for example after a deep link. The time on the previous path is only reported
+    /// if `source` is the current path.
+    pub fn navigate_from(&self, source: &str, destination: &str) {
+        self.change_path(Some(source), destination);
+    }
+
+    /// Path the user is on, if any was recorded
+    pub fn current_path(&self) -> Option<String> {
+        let state = self.client.read_state();
+        state
+            .navigation
+            .as_ref()
+            .map(|current| current.path.clone())
+    }
+
+    fn change_path(&self, source: Option<&str>, destination: &str) {
+        let now = self.client.config.clock.now();
+        let previous = self.client.write_state().navigation.replace(CurrentPath {
+            path: destination.to_string(),
+            entered_at: now,
+        });
+
+        let source = source
+            .map(str::to_string)
+            .or_else(|| previous.as_ref().map(|current| current.path.clone()))
+            .unwrap_or_default();
+        let mut signal = self
+            .client
+            .signal(signals::navigation::PATH_CHANGED)
+            .param(params::navigation::SCHEMA_VERSION, SCHEMA_VERSION)
+            .param(
+                params::navigation::IDENTIFIER,
+                format!("{source} -> {destination}"),
+            )
+            .param(params::navigation::DESTINATION_PATH, destination);
+        if let Some(previous) = previous.filter(|previous| previous.path == source) {
+            let seconds = (now - previous.entered_at)
+                .to_std()
+                .unwrap_or_default()
+                .as_secs_f64();
+            signal = signal.param(signals::signal::DURATION_IN_SECONDS, seconds);
+        }
+        signal.param(params::navigation::SOURCE_PATH, source).send();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::RecordingTransport;
+    use crate::{FixedClock, TelemetryDeck, params, signals};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    fn recorded_client(clock: &FixedClock) -> (TelemetryDeck, RecordingTransport) {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        (client.with_clock(clock.clone()), recorder)
+    }
+
+    #[tokio::test]
+    async fn sends_path_changes_with_time_on_previous_path() {
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = recorded_client(&clock);
+        let sut = client.navigator();
+        sut.navigate("/home");
+        clock.advance(Duration::from_millis(2500));
+        client.navigator().navigate("/settings");
+        client.flush().await.unwrap();
+
+        let signals = recorder.signals();
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].signal_type, signals::navigation::PATH_CHANGED);
+        assert_eq!(signals[0].param(params::navigation::SOURCE_PATH), Some(""));
+        assert_eq!(
+            signals[0].param(params::navigation::IDENTIFIER),
+            Some(" -> /home")
+        );
+        assert_eq!(signals[0].param(signals::signal::DURATION_IN_SECONDS), None);
+
+        recorder.assert_signal_sent_with(
+            signals::navigation::PATH_CHANGED,
+            &[
+                (params::navigation::SOURCE_PATH, "/home"),
+                (params::navigation::DESTINATION_PATH, "/settings"),
+                (params::navigation::IDENTIFIER, "/home -> /settings"),
+                (params::navigation::SCHEMA_VERSION, "1"),
+                (signals::signal::DURATION_IN_SECONDS, "2.5"),
+            ],
+        );
+        assert_eq!(sut.current_path(), Some("/settings".to_string()));
+    }
+
+    #[tokio::test]
+    async fn explicit_source_only_reports_its_own_time() {
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = recorded_client(&clock);
+        let sut = client.navigator();
+        sut.navigate("/home");
+        clock.advance(Duration::from_secs(3));
+        sut.navigate_from("/link", "/article");
+        client.flush().await.unwrap();
+
+        let signal = recorder.assert_signal_sent_with(
+            signals::navigation::PATH_CHANGED,
+            &[(params::navigation::IDENTIFIER, "/link -> /article")],
+        );
+        assert_eq!(signal.param(signals::signal::DURATION_IN_SECONDS), None);
+    }
+}
@@ -0,0 +1,336 @@
+//! Typed values for signal payload parameters
+//!
+//! TelemetryDeck receives every payload parameter as a `"key:value"` string. [`PayloadValue`]
+//! converts common Rust types into the same textual form the official TelemetryDeck SDKs
+//! produce, so that parameters sent from Rust group together with those sent from Swift,
+//! Kotlin or JavaScript apps.
+//!
+//! [`to_payload`] converts any `#[derive(Serialize)]` struct into payload parameters.
+
+pub use crate::ser::to_payload;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::fmt;
+
+/// A typed payload parameter value
+///
+/// Values are encoded as follows:
+///
+/// | Variant    | Example value                | Encoded as               |
+/// |------------|------------------------------|--------------------------|
+/// | `String`   | `"settings"`                 | `settings`               |
+/// | `Integer`  | `42`                         | `42`                     |
+/// | `Float`    | `2.0`, `0.25`                | `2.0`, `0.25`            |
+/// | `Bool`     | `true`                       | `true`                   |
+/// | `DateTime` | 15 Jan 2025 10:30 UTC        | `2025-01-15T10:30:00Z`   |
+/// | `List`     | `["a", "b"]`                 | `a,b`                    |
+///
+/// Thanks to the `From` implementations, values can usually be passed directly:
+///
+/// ```no_run
+/// use telemetrydeck_wasm::TelemetryDeck;
+/// use chrono::Utc;
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+///
+/// client
+///     .signal("exportFinished")
+///     .param("count", 3)
+///     .param("ratio", 0.75)
+///     .param("compressed", true)
+///     .param("startedAt", Utc::now())
+///     .param("formats", vec!["pdf", "png"])
+///     .send();
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub enum PayloadValue {
+    /// Text, sent as-is
+    String(String),
+    /// Whole number
+    Integer(i64),
+    /// Floating-point number, always encoded with a decimal point
+    Float(f64),
+    /// Boolean, encoded as `true` or `false`
+    Bool(bool),
+    /// Point in time, encoded as ISO 8601 in UTC with second precision
+    DateTime(DateTime<Utc>),
+    /// List of strings, encoded comma-separated
+    List(Vec<String>),
+}
+
+impl PayloadValue {
+    /// The canonical string encoding of this value
+    #[must_use]
+    pub fn encode(&self) -> String {
+        self.to_string()
+    }
+}
+
+impl fmt::Display for PayloadValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadValue::String(value) => f.write_str(value),
+            PayloadValue::Integer(value) => write!(f, "{}", value),
+            PayloadValue::Float(value) => {
+                let text = value.to_string();
+                if value.is_finite() && !text.contains('.') {
+                    write!(f, "{}.0", text)
+                } else {
+                    f.write_str(&text)
+                }
+            }
+            PayloadValue::Bool(value) => write!(f, "{}", value),
+            PayloadValue::DateTime(value) => {
+                f.write_str(&value.to_rfc3339_opts(SecondsFormat::Secs, true))
+            }
+            PayloadValue::List(values) => f.write_str(&values.join(",")),
+        }
+    }
+}
+
+impl From<PayloadValue> for String {
+    fn from(value: PayloadValue) -> Self {
+        value.encode()
+    }
+}
+
+impl From<String> for PayloadValue {
+    fn from(value: String) -> Self {
+        PayloadValue::String(value)
+    }
+}
+
+impl From<&str> for PayloadValue {
+    fn from(value: &str) -> Self {
+        PayloadValue::String(value.to_string())
+    }
+}
+
+impl From<&String> for PayloadValue {
+    fn from(value: &String) -> Self {
+        PayloadValue::String(value.clone())
+    }
+}
+
+macro_rules! from_integer {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for PayloadValue {
+                fn from(value: $t) -> Self {
+                    PayloadValue::Integer(i64::from(value))
+                }
+            }
+        )*
+    };
+}
+
+from_integer!(i8, i16, i32, i64, u8, u16, u32);
+
+macro_rules! from_wide_integer {
+    ($($t:ty),*) => {
+        $(
+            /// Values that do not fit in an `i64` are kept as their decimal text, which
+            /// is the same encoding
+            impl From<$t> for PayloadValue {
+                fn from(value: $t) -> Self {
+                    i64::try_from(value).map_or_else(
+                        |_| PayloadValue::String(value.to_string()),
+                        PayloadValue::Integer,
+                    )
+                }
+            }
+        )*
+    };
+}
+
+from_wide_integer!(u64, usize, isize);
+
+impl From<f32> for PayloadValue {
+    fn from(value: f32) -> Self {
+        PayloadValue::Float(f64::from(value))
+    }
+}
+
+impl From<f64> for PayloadValue {
+    fn from(value: f64) -> Self {
+        PayloadValue::Float(value)
+    }
+}
+
+impl From<bool> for PayloadValue {
+    fn from(value: bool) -> Self {
+        PayloadValue::Bool(value)
+    }
+}
+
+impl From<DateTime<Utc>> for PayloadValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        PayloadValue::DateTime(value)
+    }
+}
+
+impl From<Vec<String>> for PayloadValue {
+    fn from(value: Vec<String>) -> Self {
+        PayloadValue::List(value)
+    }
+}
+
+impl From<Vec<&str>> for PayloadValue {
+    fn from(value: Vec<&str>) -> Self {
+        PayloadValue::List(value.into_iter().map(str::to_string).collect())
+    }
+}
+
+impl From<&[&str]> for PayloadValue {
+    fn from(value: &[&str]) -> Self {
+        PayloadValue::List(value.iter().map(|v| v.to_string()).collect())
+    }
+}
+
+/// Conversion of a field value into an optional payload value
+///
+/// Used by `#[derive(TelemetrySignal)]` for every payload field. Returning `None` leaves
+/// the parameter out of the signal, which is what `Option::None` does. Implement it for
+/// your own types to use them as fields of derived signals.
+pub trait ToPayloadValue {
+    /// The payload value for this field, `None` to omit it
+    fn to_payload_value(&self) -> Option<PayloadValue>;
+}
+
+impl ToPayloadValue for PayloadValue {
+    fn to_payload_value(&self) -> Option<PayloadValue> {
+        Some(self.clone())
+    }
+}
+
+impl ToPayloadValue for str {
+    fn to_payload_value(&self) -> Option<PayloadValue> {
+        Some(PayloadValue::from(self))
+    }
+}
+
+impl ToPayloadValue for String {
+    fn to_payload_value(&self) -> Option<PayloadValue> {
+        Some(PayloadValue::from(self))
+    }
+}
+
+impl<T: ToPayloadValue + ?Sized> ToPayloadValue for &T {
+    fn to_payload_value(&self) -> Option<PayloadValue> {
+        (**self).to_payload_value()
+    }
+}
+
+impl<T: ToPayloadValue> ToPayloadValue for Option<T> {
+    fn to_payload_value(&self) -> Option<PayloadValue> {
+        self.as_ref().and_then(ToPayloadValue::to_payload_value)
+    }
+}
+
+macro_rules! to_payload_value_via_from {
+    ($($t:ty),*) => {
+        $(
+            impl ToPayloadValue for $t {
+                fn to_payload_value(&self) -> Option<PayloadValue> {
+                    Some(PayloadValue::from(self.clone()))
+                }
+            }
+        )*
+    };
+}
+
+to_payload_value_via_from!(
+    i8,
+    i16,
+    i32,
+    i64,
+    u8,
+    u16,
+    u32,
+    u64,
+    usize,
+    isize,
+    f32,
+    f64,
+    bool,
+    DateTime<Utc>,
+    Vec<String>,
+    Vec<&str>
+);
+
+macro_rules! to_payload_value_via_display {
+    ($($t:ty),*) => {
+        $(
+            impl ToPayloadValue for $t {
+                fn to_payload_value(&self) -> Option<PayloadValue> {
+                    Some(PayloadValue::String(self.to_string()))
+                }
+            }
+        )*
+    };
+}
+
+to_payload_value_via_display!(char);
+
+#[cfg(test)]
+mod tests {
+    use super::{PayloadValue, ToPayloadValue};
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn encode_scalars() {
+        assert_eq!(PayloadValue::from("settings").encode(), "settings");
+        assert_eq!(PayloadValue::from(-42).encode(), "-42");
+        assert_eq!(PayloadValue::from(7u8).encode(), "7");
+        assert_eq!(PayloadValue::from(true).encode(), "true");
+        assert_eq!(PayloadValue::from(false).encode(), "false");
+    }
+
+    #[test]
+    fn encode_wide_integers_like_small_ones() {
+        assert_eq!(PayloadValue::from(3usize), PayloadValue::Integer(3));
+        assert_eq!(PayloadValue::from(-3isize), PayloadValue::Integer(-3));
+        assert_eq!(PayloadValue::from(42u64), PayloadValue::Integer(42));
+        assert_eq!(
+            PayloadValue::from(u64::MAX).encode(),
+            "18446744073709551615"
+        );
+        assert_eq!(
+            vec!["a"; 3].len().to_payload_value(),
+            Some(PayloadValue::Integer(3))
+        );
+    }
+
+    #[test]
+    fn encode_floats_with_decimal_point() {
+        assert_eq!(PayloadValue::from(2.0).encode(), "2.0");
+        assert_eq!(PayloadValue::from(0.25).encode(), "0.25");
+        assert_eq!(PayloadValue::from(-1.5f32).encode(), "-1.5");
+        assert_eq!(PayloadValue::from(f64::NAN).encode(), "NaN");
+    }
+
+    #[test]
+    fn encode_date_as_iso8601_utc() {
+        let date = Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap();
+        assert_eq!(PayloadValue::from(date).encode(), "2025-01-15T10:30:00Z");
+    }
+
+    #[test]
+    fn encode_list_comma_separated() {
+        assert_eq!(PayloadValue::from(vec!["pdf", "png"]).encode(), "pdf,png");
+        assert_eq!(PayloadValue::from(Vec::<String>::new()).encode(), "");
+    }
+
+    #[test]
+    fn to_payload_value_omits_none() {
+        assert_eq!(None::<u32>.to_payload_value(), None);
+        assert_eq!(
+            Some(3u32).to_payload_value(),
+            Some(PayloadValue::Integer(3))
+        );
+        assert_eq!(
+            "EUR".to_payload_value(),
+            Some(PayloadValue::String("EUR".to_string()))
+        );
+    }
+}
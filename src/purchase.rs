@@ -0,0 +1,446 @@
+//! Purchase signals
+//!
+//! [`TelemetryDeck::purchase_completed`] and its siblings fill in
+//! [`params::purchase`] from a [`Purchase`]: currency codes are validated against
+//! ISO 4217 and upper-cased, the price is sent in micros, and the float value is the
+//! price in US dollars so that revenue can be summed across currencies.
+
+use crate::core::TelemetryDeck;
+use crate::error::TelemetryDeckError;
+use crate::{params, signals};
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+/// Active ISO 4217 currency codes, sorted
+const CURRENCY_CODES: &[&str] = &[
+    "AED", "AFN", "ALL", "AMD", "ANG", "AOA", "ARS", "AUD", "AWG", "AZN", "BAM", "BBD", "BDT",
+    "BGN", "BHD", "BIF", "BMD", "BND", "BOB", "BOV", "BRL", "BSD", "BTN", "BWP", "BYN", "BZD",
+    "CAD", "CDF", "CHE", "CHF", "CHW", "CLF", "CLP", "CNY", "COP", "COU", "CRC", "CUP", "CVE",
+    "CZK", "DJF", "DKK", "DOP", "DZD", "EGP", "ERN", "ETB", "EUR", "FJD", "FKP", "GBP", "GEL",
+    "GHS", "GIP", "GMD", "GNF", "GTQ", "GYD", "HKD", "HNL", "HTG", "HUF", "IDR", "ILS", "INR",
+    "IQD", "IRR", "ISK", "JMD", "JOD", "JPY", "KES", "KGS", "KHR", "KMF", "KPW", "KRW", "KWD",
+    "KYD", "KZT", "LAK", "LBP", "LKR", "LRD", "LSL", "LYD", "MAD", "MDL", "MGA", "MKD", "MMK",
+    "MNT", "MOP", "MRU", "MUR", "MVR", "MWK", "MXN", "MXV", "MYR", "MZN", "NAD", "NGN", "NIO",
+    "NOK", "NPR", "NZD", "OMR", "PAB", "PEN", "PGK", "PHP", "PKR", "PLN", "PYG", "QAR", "RON",
+    "RSD", "RUB", "RWF", "SAR", "SBD", "SCR", "SDG", "SEK", "SGD", "SHP", "SLE", "SOS", "SRD",
+    "SSP", "STN", "SVC", "SYP", "SZL", "THB", "TJS", "TMT", "TND", "TOP", "TRY", "TTD", "TWD",
+    "TZS", "UAH", "UGX", "USD", "USN", "UYI", "UYU", "UYW", "UZS", "VED", "VES", "VND", "VUV",
+    "WST", "XAF", "XCD", "XCG", "XOF", "XPF", "YER", "ZAR", "ZMW", "ZWG",
+];
+
+/// Approximate units of each currency per US dollar, used by [`ExchangeRates::bundled`]
+const BUNDLED_RATES: &[(&str, f64)] = &[
+    ("AED", 3.6725),
+    ("ARS", 1050.0),
+    ("AUD", 1.55),
+    ("BGN", 1.83),
+    ("BRL", 5.6),
+    ("CAD", 1.39),
+    ("CHF", 0.88),
+    ("CLP", 950.0),
+    ("CNY", 7.25),
+    ("COP", 4200.0),
+    ("CZK", 23.5),
+    ("DKK", 6.98),
+    ("EGP", 50.0),
+    ("EUR", 0.935),
+    ("GBP", 0.78),
+    ("HKD", 7.78),
+    ("HUF", 370.0),
+    ("IDR", 16000.0),
+    ("ILS", 3.65),
+    ("INR", 84.5),
+    ("JPY", 150.0),
+    ("KRW", 1400.0),
+    ("KZT", 500.0),
+    ("MXN", 20.0),
+    ("MYR", 4.45),
+    ("NGN", 1600.0),
+    ("NOK", 11.0),
+    ("NZD", 1.72),
+    ("PEN", 3.75),
+    ("PHP", 58.0),
+    ("PKR", 278.0),
+    ("PLN", 4.05),
+    ("QAR", 3.64),
+    ("RON", 4.65),
+    ("SAR", 3.75),
+    ("SEK", 10.8),
+    ("SGD", 1.35),
+    ("THB", 34.0),
+    ("TRY", 34.5),
+    ("TWD", 32.5),
+    ("UAH", 41.5),
+    ("USD", 1.0),
+    ("VND", 25400.0),
+    ("ZAR", 18.0),
+];
+
+static BUNDLED: LazyLock<ExchangeRates> = LazyLock::new(|| ExchangeRates {
+    per_usd: BUNDLED_RATES
+        .iter()
+        .map(|(code, rate)| (code.to_string(), *rate))
+        .collect(),
+});
+
+/// Kind of product that was bought
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PurchaseType {
+    /// Product bought once, sent as `one-time-purchase`
+    #[default]
+    OneTimePurchase,
+    /// Recurring subscription, sent as `subscription`
+    Subscription,
+}
+
+impl PurchaseType {
+    fn as_str(self) -> &'static str {
+        match self {
+            PurchaseType::OneTimePurchase => "one-time-purchase",
+            PurchaseType::Subscription => "subscription",
+        }
+    }
+}
+
+/// A purchase reported with [`TelemetryDeck::purchase_completed`]
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{Purchase, PurchaseType, TelemetryDeck};
+///
+/// # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+///
+/// client.purchase_completed(Purchase {
+///     product_id: "pro.yearly".to_string(),
+///     price: 49.99,
+///     currency_code: "EUR".to_string(),
+///     purchase_type: PurchaseType::Subscription,
+///     country_code: Some("BE".to_string()),
+///     ..Purchase::default()
+/// })?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Purchase {
+    /// Identifier of the product in the store
+    pub product_id: String,
+    /// Price paid, in units of the currency (`4.99`, not cents)
+    pub price: f64,
+    /// ISO 4217 code of the currency, such as `EUR`; case does not matter
+    pub currency_code: String,
+    /// Kind of product
+    pub purchase_type: PurchaseType,
+    /// ISO 3166-1 alpha-2 code of the store's country, such as `BE`
+    pub country_code: Option<String>,
+    /// Identifier of the offer or promotion used, if any
+    pub offer_id: Option<String>,
+}
+
+/// Conversion rates used to compute the US dollar value of purchases
+///
+/// The client uses [`bundled`](Self::bundled) rates unless others are configured with
+/// [`TelemetryDeck::with_exchange_rates`]. Purchases in a currency without a rate are
+/// sent without a float value.
+///
+/// # Examples
+///
+/// ```
+/// use telemetrydeck_wasm::ExchangeRates;
+///
+/// # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+/// // Units of each currency per US dollar, for example from the finance team
+/// let rates = ExchangeRates::new([("EUR", 0.9), ("GBP", 0.8)])?;
+/// assert_eq!(rates.to_usd(9.0, "eur"), Some(10.0));
+/// assert_eq!(rates.to_usd(9.0, "JPY"), None);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExchangeRates {
+    /// Units of each currency per US dollar, by upper-case code
+    per_usd: HashMap<String, f64>,
+}
+
+impl ExchangeRates {
+    /// Rates from units of each currency per US dollar
+    ///
+    /// US dollars always convert at `1.0` unless a rate is given for `USD`.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TelemetryDeckError::InvalidPurchase`] if a code is not an ISO 4217
+    /// currency code, or a rate is not a positive number.
+    pub fn new<I, K>(rates: I) -> Result<Self, TelemetryDeckError>
+    where
+        I: IntoIterator<Item = (K, f64)>,
+        K: AsRef<str>,
+    {
+        let mut per_usd = HashMap::from([("USD".to_string(), 1.0)]);
+        for (code, rate) in rates {
+            let code = currency_code(code.as_ref())?;
+            if !(rate.is_finite() && rate > 0.0) {
+                return Err(TelemetryDeckError::InvalidPurchase(format!(
+                    "exchange rate of {code} must be a positive number, got {rate}"
+                )));
+            }
+            per_usd.insert(code, rate);
+        }
+        Ok(ExchangeRates { per_usd })
+    }
+
+    /// Approximate rates shipped with this crate
+    ///
+    /// Covers the most common store currencies. The rates are not updated at runtime,
+    /// so the US dollar values are estimates; configure your own rates when they need
+    /// to be accurate.
+    #[must_use]
+    pub fn bundled() -> Self {
+        BUNDLED.clone()
+    }
+
+    /// `amount` of `currency_code` in US dollars, rounded to cents
+    pub fn to_usd(&self, amount: f64, currency_code: &str) -> Option<f64> {
+        let rate = self.per_usd.get(&currency_code.to_ascii_uppercase())?;
+        Some((amount / rate * 100.0).round() / 100.0)
+    }
+}
+
+impl Default for ExchangeRates {
+    fn default() -> Self {
+        Self::bundled()
+    }
+}
+
+impl TelemetryDeck {
+    /// Send [`signals::purchase::COMPLETED`] for `purchase` (fire-and-forget)
+    ///
+    /// Sets [`params::purchase`] `PRODUCT_ID`, `TYPE`, `PRICE_MICROS`, `CURRENCY_CODE`
+    /// and, when given, `COUNTRY_CODE` and `OFFER_ID`. The float value is the price in
+    /// US dollars, see [`ExchangeRates`].
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TelemetryDeckError::InvalidPurchase`], without sending anything, if the
+    /// product id is empty, the price is negative or not finite, or a currency or
+    /// country code is not valid.
+    pub fn purchase_completed(&self, purchase: Purchase) -> Result<(), TelemetryDeckError> {
+        self.send_purchase(signals::purchase::COMPLETED, purchase)
+    }
+
+    /// Send [`signals::purchase::CONVERTED_FROM_TRIAL`] for the first paid period of a
+    /// subscription that started with a free trial (fire-and-forget)
+    ///
+    /// Sets the same parameters as [`purchase_completed`](Self::purchase_completed).
+    ///
+    /// # Errors
+    ///
+    /// Same as [`purchase_completed`](Self::purchase_completed).
+    pub fn converted_from_trial(&self, purchase: Purchase) -> Result<(), TelemetryDeckError> {
+        self.send_purchase(signals::purchase::CONVERTED_FROM_TRIAL, purchase)
+    }
+
+    /// Send [`signals::purchase::FREE_TRIAL_STARTED`] for `product_id` (fire-and-forget)
+    ///
+    /// Sets [`params::purchase::PRODUCT_ID`], and [`params::purchase::OFFER_ID`] when
+    /// given.
+    pub fn free_trial_started(&self, product_id: &str, offer_id: Option<&str>) {
+        let mut signal = self
+            .signal(signals::purchase::FREE_TRIAL_STARTED)
+            .param(params::purchase::PRODUCT_ID, product_id);
+        if let Some(offer_id) = offer_id {
+            signal = signal.param(params::purchase::OFFER_ID, offer_id);
+        }
+        signal.send();
+    }
+
+    fn send_purchase(
+        &self,
+        signal_type: &str,
+        purchase: Purchase,
+    ) -> Result<(), TelemetryDeckError> {
+        if purchase.product_id.is_empty() {
+            return Err(TelemetryDeckError::InvalidPurchase(
+                "product id must not be empty".to_string(),
+            ));
+        }
+        if !(purchase.price.is_finite() && purchase.price >= 0.0) {
+            return Err(TelemetryDeckError::InvalidPurchase(format!(
+                "price must be a non-negative number, got {}",
+                purchase.price
+            )));
+        }
+        let currency = currency_code(&purchase.currency_code)?;
+        let country = purchase
+            .country_code
+            .as_deref()
+            .map(country_code)
+            .transpose()?;
+
+        let rates = self.config.exchange_rates.as_deref().unwrap_or(&BUNDLED);
+        let mut signal = self
+            .signal(signal_type)
+            .param(params::purchase::PRODUCT_ID, purchase.product_id)
+            .param(params::purchase::TYPE, purchase.purchase_type.as_str())
+            .param(
+                params::purchase::PRICE_MICROS,
+                (purchase.price * 1_000_000.0).round() as i64,
+            );
+        if let Some(usd) = rates.to_usd(purchase.price, &currency) {
+            signal = signal.float(usd);
+        }
+        signal = signal.param(params::purchase::CURRENCY_CODE, currency);
+        if let Some(country) = country {
+            signal = signal.param(params::purchase::COUNTRY_CODE, country);
+        }
+        if let Some(offer_id) = purchase.offer_id {
+            signal = signal.param(params::purchase::OFFER_ID, offer_id);
+        }
+        signal.send();
+        Ok(())
+    }
+}
+
+/// Upper-cased ISO 4217 currency code
+fn currency_code(code: &str) -> Result<String, TelemetryDeckError> {
+    let code = code.trim().to_ascii_uppercase();
+    if CURRENCY_CODES.binary_search(&code.as_str()).is_err() {
+        return Err(TelemetryDeckError::InvalidPurchase(format!(
+            "`{code}` is not an ISO 4217 currency code"
+        )));
+    }
+    Ok(code)
+}
+
+/// Upper-cased ISO 3166-1 alpha-2 country code
+fn country_code(code: &str) -> Result<String, TelemetryDeckError> {
+    let code = code.trim().to_ascii_uppercase();
+    if code.len() != 2 || !code.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(TelemetryDeckError::InvalidPurchase(format!(
+            "`{code}` is not an ISO 3166-1 alpha-2 country code"
+        )));
+    }
+    Ok(code)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BUNDLED_RATES, CURRENCY_CODES, ExchangeRates, Purchase, PurchaseType};
+    use crate::{TelemetryDeck, TelemetryDeckError, params, signals};
+
+    fn yearly(currency_code: &str) -> Purchase {
+        Purchase {
+            product_id: "pro.yearly".to_string(),
+            price: 49.99,
+            currency_code: currency_code.to_string(),
+            purchase_type: PurchaseType::Subscription,
+            ..Purchase::default()
+        }
+    }
+
+    #[test]
+    fn tables_are_sorted_iso_codes() {
+        assert!(CURRENCY_CODES.windows(2).all(|pair| pair[0] < pair[1]));
+        for (code, rate) in BUNDLED_RATES {
+            assert!(CURRENCY_CODES.binary_search(code).is_ok(), "{code}");
+            assert!(*rate > 0.0, "{code}");
+        }
+    }
+
+    #[tokio::test]
+    async fn sends_normalized_purchase() {
+        let rates = ExchangeRates::new([("EUR", 0.5)]).unwrap();
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        let client = client.with_exchange_rates(rates);
+        client
+            .purchase_completed(Purchase {
+                country_code: Some("be".to_string()),
+                offer_id: Some("launch".to_string()),
+                ..yearly(" eur")
+            })
+            .unwrap();
+        client.flush().await.unwrap();
+
+        let signal = recorder.assert_signal_sent_with(
+            signals::purchase::COMPLETED,
+            &[
+                (params::purchase::PRODUCT_ID, "pro.yearly"),
+                (params::purchase::TYPE, "subscription"),
+                (params::purchase::PRICE_MICROS, "49990000"),
+                (params::purchase::CURRENCY_CODE, "EUR"),
+                (params::purchase::COUNTRY_CODE, "BE"),
+                (params::purchase::OFFER_ID, "launch"),
+            ],
+        );
+        assert_eq!(signal.float_value, Some(99.98));
+    }
+
+    #[tokio::test]
+    async fn currency_without_rate_has_no_float_value() {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        client.converted_from_trial(yearly("ISK")).unwrap();
+        client.free_trial_started("pro.yearly", None);
+        client.flush().await.unwrap();
+
+        let signal = recorder.assert_signal_sent_with(
+            signals::purchase::CONVERTED_FROM_TRIAL,
+            &[(params::purchase::CURRENCY_CODE, "ISK")],
+        );
+        assert_eq!(signal.float_value, None);
+        let signal = recorder.assert_signal_sent_with(
+            signals::purchase::FREE_TRIAL_STARTED,
+            &[(params::purchase::PRODUCT_ID, "pro.yearly")],
+        );
+        assert_eq!(signal.param(params::purchase::OFFER_ID), None);
+    }
+
+    #[tokio::test]
+    async fn invalid_purchases_are_not_sent() {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        let invalid = [
+            yearly("EURO"),
+            yearly("XYZ"),
+            Purchase {
+                price: -1.0,
+                ..yearly("USD")
+            },
+            Purchase {
+                price: f64::NAN,
+                ..yearly("USD")
+            },
+            Purchase {
+                product_id: String::new(),
+                ..yearly("USD")
+            },
+            Purchase {
+                country_code: Some("BEL".to_string()),
+                ..yearly("USD")
+            },
+        ];
+        for purchase in invalid {
+            let result = client.purchase_completed(purchase.clone());
+            assert!(
+                matches!(result, Err(TelemetryDeckError::InvalidPurchase(_))),
+                "{purchase:?}"
+            );
+        }
+        client.flush().await.unwrap();
+        recorder.assert_signal_not_sent(signals::purchase::COMPLETED);
+    }
+
+    #[test]
+    fn exchange_rates_validate_input() {
+        let bundled = ExchangeRates::bundled();
+        assert_eq!(bundled.to_usd(10.0, "usd"), Some(10.0));
+        assert!(ExchangeRates::new([("EUR", 0.0)]).is_err());
+        assert!(ExchangeRates::new([("EURO", 1.0)]).is_err());
+        assert_eq!(
+            ExchangeRates::new([("JPY", 150.0)])
+                .unwrap()
+                .to_usd(1000.0, "JPY"),
+            Some(6.67)
+        );
+    }
+}
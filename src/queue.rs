@@ -0,0 +1,319 @@
+//! In-memory batching of outgoing signals
+//!
+//! Signals passed to [`TelemetryDeck::send`](crate::TelemetryDeck::send) are not posted
+//! one by one. They are collected in a queue owned by the client and delivered as a single
+//! request once the queue reaches [`BatchConfig::max_batch_size`] signals, or once
+//! [`BatchConfig::flush_interval`] has elapsed since the first signal was queued,
+//! whichever happens first.
+//!
+//! The queue holds at most [`BatchConfig::max_queue_size`] signals. Signals sent while it
+//! is full are dropped, and the next call to
+//! [`TelemetryDeck::flush`](crate::TelemetryDeck::flush) reports them as
+//! [`TelemetryDeckError::QueueOverflow`]. Background flushes leave that report to it.
+
+use crate::core::Signal;
+use crate::error::TelemetryDeckError;
+use std::future::Future;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// Batching configuration for fire-and-forget signals
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{BatchConfig, TelemetryDeck};
+/// use std::time::Duration;
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_batch_config(BatchConfig {
+///     max_batch_size: 20,
+///     flush_interval: Duration::from_secs(2),
+///     ..BatchConfig::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchConfig {
+    /// Number of queued signals that triggers an immediate flush
+    ///
+    /// This is also the maximum number of signals posted in a single request.
+    /// Default: `50`
+    pub max_batch_size: usize,
+
+    /// Maximum time a signal waits in the queue before it is flushed
+    ///
+    /// The timer starts when a signal is added to an empty queue.
+    /// Default: 5 seconds
+    pub flush_interval: Duration,
+
+    /// Maximum number of signals waiting to be delivered
+    ///
+    /// Signals sent while the queue is full are dropped.
+    /// Default: `10_000`
+    pub max_queue_size: usize,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        BatchConfig {
+            max_batch_size: 50,
+            flush_interval: Duration::from_secs(5),
+            max_queue_size: 10_000,
+        }
+    }
+}
+
+/// What the caller should do after a signal was queued
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum QueueAction {
+    /// The batch is full and should be flushed right away
+    FlushNow,
+    /// This is the first signal of a new batch, a delayed flush should be scheduled
+    ScheduleFlush,
+    /// A flush is already scheduled, nothing to do
+    None,
+}
+
+/// Queue of signals waiting to be delivered
+#[derive(Debug)]
+pub(crate) struct SignalQueue {
+    config: BatchConfig,
+    pending: Mutex<Vec<Signal>>,
+    flush_scheduled: AtomicBool,
+    dropped: AtomicUsize,
+    /// Held for the whole duration of a flush so that batches are delivered in order
+    /// and [`SignalQueue::flush`] can wait for deliveries already in progress.
+    delivery: futures::lock::Mutex<()>,
+}
+
+impl SignalQueue {
+    pub(crate) fn new(config: BatchConfig) -> Self {
+        SignalQueue {
+            config: BatchConfig {
+                max_batch_size: config.max_batch_size.max(1),
+                ..config
+            },
+            pending: Mutex::new(Vec::new()),
+            flush_scheduled: AtomicBool::new(false),
+            dropped: AtomicUsize::new(0),
+            delivery: futures::lock::Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn config(&self) -> BatchConfig {
+        self.config
+    }
+
+    /// Add a signal to the queue, or drop it if the queue is full
+    ///
+    /// A full queue already holds a whole batch, whose flush was requested by an
+    /// earlier push, so dropping a signal never asks for another one.
+    pub(crate) fn push(&self, signal: Signal) -> QueueAction {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        if pending.len() >= self.config.max_queue_size {
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+            return QueueAction::None;
+        }
+        pending.push(signal);
+        if pending.len() >= self.config.max_batch_size {
+            QueueAction::FlushNow
+        } else if !self.flush_scheduled.swap(true, Ordering::AcqRel) {
+            QueueAction::ScheduleFlush
+        } else {
+            QueueAction::None
+        }
+    }
+
+    fn take_all(&self) -> Vec<Signal> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        self.flush_scheduled.store(false, Ordering::Release);
+        std::mem::take(&mut *pending)
+    }
+
+    /// Report the signals dropped since the last call as
+    /// [`TelemetryDeckError::QueueOverflow`]
+    pub(crate) fn take_overflow(&self) -> Result<(), TelemetryDeckError> {
+        match self.dropped.swap(0, Ordering::Relaxed) {
+            0 => Ok(()),
+            dropped => Err(TelemetryDeckError::QueueOverflow { dropped }),
+        }
+    }
+
+    /// Deliver every signal queued so far, in batches of at most `max_batch_size`
+    ///
+    /// Waits for flushes that are already in progress before taking the queue, so
+    /// once this returns every signal queued before the call has been handed to
+    /// `deliver`. Every batch is attempted; the first error is returned. Dropped
+    /// signals are not reported here, see [`take_overflow`](Self::take_overflow).
+    pub(crate) async fn flush<F, Fut>(&self, mut deliver: F) -> Result<(), TelemetryDeckError>
+    where
+        F: FnMut(Vec<Signal>) -> Fut,
+        Fut: Future<Output = Result<(), TelemetryDeckError>>,
+    {
+        let _delivery = self.delivery.lock().await;
+        let mut signals = self.take_all();
+        let mut result = Ok(());
+        while !signals.is_empty() {
+            let rest = signals.split_off(signals.len().min(self.config.max_batch_size));
+            let outcome = deliver(signals).await;
+            if result.is_ok() {
+                result = outcome;
+            }
+            signals = rest;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BatchConfig, QueueAction, SignalQueue};
+    use crate::{TelemetryDeck, TelemetryDeckError};
+    use std::time::Duration;
+
+    fn queue(max_batch_size: usize) -> SignalQueue {
+        SignalQueue::new(BatchConfig {
+            max_batch_size,
+            flush_interval: Duration::from_secs(60),
+            max_queue_size: 100,
+        })
+    }
+
+    #[test]
+    fn push_schedules_flush_once() {
+        let client = TelemetryDeck::new("1234");
+        let sut = queue(10);
+        let first = sut.push(client.create_signal("a", None, None, None, None));
+        let second = sut.push(client.create_signal("b", None, None, None, None));
+        assert_eq!(first, QueueAction::ScheduleFlush);
+        assert_eq!(second, QueueAction::None);
+        assert_eq!(sut.take_all().len(), 2);
+    }
+
+    #[test]
+    fn push_requests_flush_when_full() {
+        let client = TelemetryDeck::new("1234");
+        let sut = queue(2);
+        sut.push(client.create_signal("a", None, None, None, None));
+        let action = sut.push(client.create_signal("b", None, None, None, None));
+        assert_eq!(action, QueueAction::FlushNow);
+    }
+
+    #[tokio::test]
+    async fn flush_delivers_in_batches() {
+        let client = TelemetryDeck::new("1234");
+        let sut = queue(2);
+        for name in ["a", "b", "c"] {
+            sut.push(client.create_signal(name, None, None, None, None));
+        }
+
+        let mut batches = Vec::new();
+        let result = sut
+            .flush(|batch| {
+                batches.push(batch.into_iter().map(|s| s.signal_type).collect::<Vec<_>>());
+                async { Ok(()) }
+            })
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(batches, vec![vec!["a", "b"], vec!["c"]]);
+        assert!(sut.take_all().is_empty());
+    }
+
+    #[tokio::test]
+    async fn flush_attempts_every_batch_and_returns_first_error() {
+        let client = TelemetryDeck::new("1234");
+        let sut = queue(1);
+        for name in ["a", "b"] {
+            sut.push(client.create_signal(name, None, None, None, None));
+        }
+
+        let mut attempts = 0;
+        let result = sut
+            .flush(|batch| {
+                attempts += 1;
+                let status = if batch[0].signal_type == "a" {
+                    400
+                } else {
+                    500
+                };
+                async move {
+                    Err(TelemetryDeckError::HttpStatus {
+                        status,
+                        body: String::new(),
+                        retry_after: None,
+                    })
+                }
+            })
+            .await;
+
+        assert_eq!(attempts, 2);
+        assert!(matches!(
+            result,
+            Err(TelemetryDeckError::HttpStatus { status: 400, .. })
+        ));
+    }
+
+    #[test]
+    fn flush_rearms_scheduling() {
+        let client = TelemetryDeck::new("1234");
+        let sut = queue(10);
+        sut.push(client.create_signal("a", None, None, None, None));
+        let _ = sut.take_all();
+        let action = sut.push(client.create_signal("b", None, None, None, None));
+        assert_eq!(action, QueueAction::ScheduleFlush);
+    }
+
+    #[tokio::test]
+    async fn full_queue_drops_signals_and_reports_overflow() {
+        let client = TelemetryDeck::new("1234");
+        let sut = SignalQueue::new(BatchConfig {
+            max_batch_size: 10,
+            flush_interval: Duration::from_secs(60),
+            max_queue_size: 2,
+        });
+        for name in ["a", "b", "c"] {
+            sut.push(client.create_signal(name, None, None, None, None));
+        }
+        let action = sut.push(client.create_signal("d", None, None, None, None));
+        assert_eq!(action, QueueAction::None);
+
+        let mut delivered = 0;
+        let result = sut
+            .flush(|batch| {
+                delivered += batch.len();
+                async { Ok(()) }
+            })
+            .await;
+
+        assert!(result.is_ok());
+        assert_eq!(delivered, 2);
+        assert!(matches!(
+            sut.take_overflow(),
+            Err(TelemetryDeckError::QueueOverflow { dropped: 2 })
+        ));
+        assert!(sut.take_overflow().is_ok());
+    }
+
+    #[tokio::test]
+    async fn client_flush_reports_signals_dropped_before_a_background_flush() {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        let client = client.with_batch_config(BatchConfig {
+            max_batch_size: 2,
+            flush_interval: Duration::from_secs(60),
+            max_queue_size: 2,
+        });
+        for name in ["a", "b", "c", "d", "e"] {
+            client.send(name, None, None, None, None);
+        }
+        // Let the flush requested by the full batch run first
+        tokio::task::yield_now().await;
+        assert_eq!(recorder.signals().len(), 2);
+
+        assert!(matches!(
+            client.flush().await,
+            Err(TelemetryDeckError::QueueOverflow { dropped: 3 })
+        ));
+        assert!(client.flush().await.is_ok());
+    }
+}
@@ -0,0 +1,335 @@
+//! Retention metrics computed from the session history
+//!
+//! See [`TelemetryDeckBuilder::retention`](crate::TelemetryDeckBuilder::retention) for
+//! the computed parameters.
+
+use crate::params;
+use crate::store::KeyValueStore;
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Key of the session history in the store
+const HISTORY_KEY: &str = "TelemetryDeck.Retention.history";
+
+/// Sessions older than this are forgotten, except for the counters
+const SESSION_HISTORY_DAYS: i64 = 90;
+
+/// Days counted by `DISTINCT_DAYS_USED_LAST_MONTH`
+const LAST_MONTH_DAYS: i64 = 30;
+
+/// Minimum time between two writes of the current session's duration
+const SAVE_INTERVAL: TimeDelta = TimeDelta::seconds(60);
+
+/// Session history as stored
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RetentionHistory {
+    total_sessions: u64,
+    distinct_days: Vec<NaiveDate>,
+    sessions: Vec<StoredSession>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StoredSession {
+    started_at: DateTime<Utc>,
+    duration_seconds: u64,
+}
+
+/// Records sessions in a [`KeyValueStore`] and computes retention parameters
+#[derive(Debug)]
+pub(crate) struct RetentionTracker {
+    store: Arc<dyn KeyValueStore>,
+    state: Mutex<TrackerState>,
+    /// Held while writing, so writes reach the store in order
+    save_lock: Mutex<()>,
+}
+
+#[derive(Debug)]
+struct TrackerState {
+    history: RetentionHistory,
+    saved_at: Option<DateTime<Utc>>,
+    /// Whether [`RetentionTracker::persist`] should write the history
+    save_due: bool,
+}
+
+impl RetentionTracker {
+    /// Load the history kept in `store`; unreadable history starts over
+    pub(crate) fn load(store: Arc<dyn KeyValueStore>) -> Self {
+        let history = store
+            .get(HISTORY_KEY)
+            .ok()
+            .flatten()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+        RetentionTracker {
+            store,
+            state: Mutex::new(TrackerState {
+                history,
+                saved_at: None,
+                save_due: false,
+            }),
+            save_lock: Mutex::new(()),
+        }
+    }
+
+    /// Record a session started at `now` and return the retention parameters
+    ///
+    /// `previous_seconds` is the exact duration of the session that just ended in this
+    /// process, if any. The history is written by the next [`persist`](Self::persist).
+    pub(crate) fn session_started(
+        &self,
+        now: DateTime<Utc>,
+        previous_seconds: Option<u64>,
+    ) -> HashMap<String, String> {
+        let mut state = self.lock();
+        let history = &mut state.history;
+        if let (Some(seconds), Some(previous)) = (previous_seconds, history.sessions.last_mut()) {
+            previous.duration_seconds = seconds;
+        }
+
+        let cutoff = now - TimeDelta::days(SESSION_HISTORY_DAYS);
+        history
+            .sessions
+            .retain(|session| session.started_at > cutoff);
+        let durations: Vec<u64> = history
+            .sessions
+            .iter()
+            .map(|session| session.duration_seconds)
+            .collect();
+
+        history.total_sessions += 1;
+        history.sessions.push(StoredSession {
+            started_at: now,
+            duration_seconds: 0,
+        });
+        let today = now.date_naive();
+        if !history.distinct_days.contains(&today) {
+            history.distinct_days.push(today);
+        }
+
+        let month_start = today - TimeDelta::days(LAST_MONTH_DAYS);
+        let days_last_month = history
+            .distinct_days
+            .iter()
+            .filter(|day| **day > month_start)
+            .count();
+        let mut result = HashMap::from([
+            (
+                params::retention::TOTAL_SESSIONS_COUNT.to_string(),
+                history.total_sessions.to_string(),
+            ),
+            (
+                params::retention::DISTINCT_DAYS_USED.to_string(),
+                history.distinct_days.len().to_string(),
+            ),
+            (
+                params::retention::DISTINCT_DAYS_USED_LAST_MONTH.to_string(),
+                days_last_month.to_string(),
+            ),
+        ]);
+        if let Some(last) = durations.last() {
+            let average = durations.iter().sum::<u64>() / durations.len() as u64;
+            result.insert(
+                params::retention::AVERAGE_SESSION_SECONDS.to_string(),
+                average.to_string(),
+            );
+            result.insert(
+                params::retention::PREVIOUS_SESSION_SECONDS.to_string(),
+                last.to_string(),
+            );
+        }
+
+        state.save_due = true;
+        result
+    }
+
+    /// Record that the current session lasted `session_seconds` so far
+    ///
+    /// Written to the store by [`persist`](Self::persist) at most once per
+    /// [`SAVE_INTERVAL`].
+    pub(crate) fn session_continued(&self, now: DateTime<Utc>, session_seconds: u64) {
+        let mut state = self.lock();
+        let Some(current) = state.history.sessions.last_mut() else {
+            return;
+        };
+        current.duration_seconds = session_seconds;
+        if state
+            .saved_at
+            .is_none_or(|saved_at| now - saved_at >= SAVE_INTERVAL)
+        {
+            state.save_due = true;
+        }
+    }
+
+    /// Write the history if it changed enough since the last write
+    ///
+    /// Called without holding the client's state lock, since the store may do file
+    /// I/O. Failures are ignored and retried by the next call.
+    pub(crate) fn persist(&self, now: DateTime<Utc>) {
+        let _saving = self.save_lock.lock().unwrap_or_else(|e| e.into_inner());
+        let json = {
+            let mut state = self.lock();
+            if !std::mem::take(&mut state.save_due) {
+                return;
+            }
+            serde_json::to_string(&state.history)
+        };
+        let saved = json.is_ok_and(|json| self.store.set(HISTORY_KEY, json).is_ok());
+        let mut state = self.lock();
+        if saved {
+            state.saved_at = Some(now);
+        } else {
+            state.save_due = true;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TrackerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::RecordingTransport;
+    use crate::{FixedClock, MemoryStore, SessionConfig, TelemetryDeck, params, signals};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    fn tracked_client(
+        store: &MemoryStore,
+        clock: &FixedClock,
+    ) -> (TelemetryDeck, RecordingTransport) {
+        let (builder, recorder) = TelemetryDeck::recording_builder("1234");
+        let client = builder
+            .clock(clock.clone())
+            .session_tracking(SessionConfig {
+                idle_timeout: Duration::from_secs(60),
+            })
+            .retention(store.clone())
+            .build()
+            .unwrap();
+        (client, recorder)
+    }
+
+    #[tokio::test]
+    async fn first_session_counts_itself() {
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = tracked_client(&MemoryStore::new(), &clock);
+        client.send("appOpened", None, None, None, None);
+        client.flush().await.unwrap();
+
+        let signal = recorder.assert_signal_sent_with(
+            signals::session::STARTED,
+            &[
+                (params::retention::TOTAL_SESSIONS_COUNT, "1"),
+                (params::retention::DISTINCT_DAYS_USED, "1"),
+                (params::retention::DISTINCT_DAYS_USED_LAST_MONTH, "1"),
+            ],
+        );
+        assert_eq!(
+            signal.param(params::retention::AVERAGE_SESSION_SECONDS),
+            None
+        );
+        assert_eq!(
+            signal.param(params::retention::PREVIOUS_SESSION_SECONDS),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn history_is_kept_across_clients() {
+        let store = MemoryStore::new();
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, _) = tracked_client(&store, &clock);
+        client.send("appOpened", None, None, None, None);
+        clock.advance(Duration::from_secs(40));
+        client.send("appOpened", None, None, None, None);
+        clock.advance(Duration::from_secs(120));
+        client.send("appOpened", None, None, None, None);
+        for _ in 0..2 {
+            clock.advance(Duration::from_secs(50));
+            client.send("appOpened", None, None, None, None);
+        }
+        drop(client);
+
+        // Next run, 40 days later
+        clock.advance(Duration::from_secs(40 * 24 * 60 * 60));
+        let (client, recorder) = tracked_client(&store, &clock);
+        client.send("appOpened", None, None, None, None);
+        client.flush().await.unwrap();
+
+        recorder.assert_signal_sent_with(
+            "appOpened",
+            &[
+                (params::retention::TOTAL_SESSIONS_COUNT, "3"),
+                (params::retention::DISTINCT_DAYS_USED, "2"),
+                (params::retention::DISTINCT_DAYS_USED_LAST_MONTH, "1"),
+                (params::retention::AVERAGE_SESSION_SECONDS, "70"),
+                (params::retention::PREVIOUS_SESSION_SECONDS, "100"),
+            ],
+        );
+    }
+
+    #[tokio::test]
+    async fn unreadable_history_starts_over() {
+        use crate::KeyValueStore;
+
+        let store = MemoryStore::new();
+        store
+            .set("TelemetryDeck.Retention.history", "not json".to_string())
+            .unwrap();
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = tracked_client(&store, &clock);
+        client.send("appOpened", None, None, None, None);
+        client.flush().await.unwrap();
+        recorder.assert_signal_sent_with(
+            "appOpened",
+            &[(params::retention::TOTAL_SESSIONS_COUNT, "1")],
+        );
+    }
+
+    #[tokio::test]
+    async fn history_is_written_without_holding_the_client_state() {
+        use crate::KeyValueStore;
+        use crate::error::TelemetryDeckError;
+        use std::sync::{Arc, OnceLock};
+
+        #[derive(Debug, Default)]
+        struct CheckingStore {
+            inner: MemoryStore,
+            client: OnceLock<TelemetryDeck>,
+        }
+
+        impl KeyValueStore for CheckingStore {
+            fn get(&self, key: &str) -> Result<Option<String>, TelemetryDeckError> {
+                self.inner.get(key)
+            }
+
+            fn set(&self, key: &str, value: String) -> Result<(), TelemetryDeckError> {
+                let client = self.client.get().unwrap();
+                assert!(client.state.try_write().is_ok(), "state locked during I/O");
+                self.inner.set(key, value)
+            }
+        }
+
+        let store = Arc::new(CheckingStore::default());
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (builder, _) = TelemetryDeck::recording_builder("1234");
+        let client = builder
+            .clock(clock.clone())
+            .session_tracking(SessionConfig::default())
+            .retention(Arc::clone(&store))
+            .build()
+            .unwrap();
+        store.client.set(client.clone()).unwrap();
+        client.send("appOpened", None, None, None, None);
+        clock.advance(Duration::from_secs(90));
+        client.send("appOpened", None, None, None, None);
+
+        let history = store.inner.get("TelemetryDeck.Retention.history").unwrap();
+        assert!(history.unwrap().contains("\"durationSeconds\":90"));
+    }
+}
@@ -0,0 +1,292 @@
+//! Retrying failed deliveries with exponential backoff
+//!
+//! Every batch posted by the client, whether from [`TelemetryDeck::send`](crate::TelemetryDeck::send),
+//! [`TelemetryDeck::send_sync`](crate::TelemetryDeck::send_sync) or a flush, goes through the
+//! same [`RetryPolicy`]. Connection errors and `408`, `429` and `5xx` responses are retried;
+//! any other `4xx` response is a permanent failure and is reported right away.
+
+use crate::error::TelemetryDeckError;
+use chrono::{DateTime, Utc};
+use std::future::Future;
+use std::time::Duration;
+
+/// Retry policy for failed deliveries
+///
+/// The delay before retry `n` (starting at 1) is `base_delay * 2^(n - 1)`, capped at
+/// `max_delay`. A random part of up to `jitter` times that delay is then subtracted, so
+/// that many clients failing at the same time do not retry in lockstep.
+///
+/// When the server answers with a `Retry-After` header, its value is used instead of the
+/// computed delay (still capped at `max_delay`, without jitter).
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{RetryPolicy, TelemetryDeck};
+/// use std::time::Duration;
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_retry_policy(RetryPolicy {
+///     max_attempts: 5,
+///     base_delay: Duration::from_millis(200),
+///     max_delay: Duration::from_secs(10),
+///     jitter: 0.5,
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    ///
+    /// `1` disables retries. Default: `3`
+    pub max_attempts: u32,
+
+    /// Delay before the first retry
+    ///
+    /// Default: 500 milliseconds
+    pub base_delay: Duration,
+
+    /// Upper bound for any single delay
+    ///
+    /// Default: 30 seconds
+    pub max_delay: Duration,
+
+    /// Fraction of the delay that is randomized, between `0.0` and `1.0`
+    ///
+    /// Values outside that range are clamped; `NaN` and infinities disable jitter.
+    /// Default: `0.5`
+    pub jitter: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            jitter: 0.5,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes a single attempt and never retries
+    #[must_use]
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Delay to wait after the failed attempt number `attempt` (starting at 1)
+    pub(crate) fn delay_for(&self, attempt: u32, retry_after: Option<Duration>) -> Duration {
+        if let Some(retry_after) = retry_after {
+            return retry_after.min(self.max_delay);
+        }
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        // `with_retry_policy` does not validate the policy, and `mul_f64(NaN)` panics
+        let jitter = if self.jitter.is_finite() {
+            self.jitter.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        if jitter == 0.0 {
+            delay
+        } else {
+            delay.mul_f64(1.0 - jitter * fastrand::f64())
+        }
+    }
+}
+
+/// Parse a `Retry-After` header value, either delay-seconds or an HTTP date
+pub(crate) fn parse_retry_after(value: &str, now: DateTime<Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        date.with_timezone(&Utc)
+            .signed_duration_since(now)
+            .to_std()
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Run `attempt` until it succeeds, fails permanently, or the policy is exhausted
+pub(crate) async fn with_retries<A, AFut, S, SFut>(
+    policy: RetryPolicy,
+    mut attempt: A,
+    mut sleep: S,
+) -> Result<(), TelemetryDeckError>
+where
+    A: FnMut() -> AFut,
+    AFut: Future<Output = Result<(), TelemetryDeckError>>,
+    S: FnMut(Duration) -> SFut,
+    SFut: Future<Output = ()>,
+{
+    let mut attempt_number = 1;
+    loop {
+        match attempt().await {
+            Ok(()) => return Ok(()),
+            Err(e) if e.is_retryable() && attempt_number < policy.max_attempts => {
+                sleep(policy.delay_for(attempt_number, e.retry_after())).await;
+                attempt_number += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RetryPolicy, parse_retry_after, with_retries};
+    use crate::error::TelemetryDeckError;
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    fn status(status: u16) -> TelemetryDeckError {
+        TelemetryDeckError::HttpStatus {
+            status,
+            body: String::new(),
+            retry_after: None,
+        }
+    }
+
+    fn policy(max_attempts: u32, jitter: f64) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1000),
+            jitter,
+        }
+    }
+
+    #[test]
+    fn delay_grows_exponentially_up_to_cap() {
+        let sut = policy(10, 0.0);
+        assert_eq!(sut.delay_for(1, None), Duration::from_millis(100));
+        assert_eq!(sut.delay_for(2, None), Duration::from_millis(200));
+        assert_eq!(sut.delay_for(3, None), Duration::from_millis(400));
+        assert_eq!(sut.delay_for(5, None), Duration::from_millis(1000));
+        assert_eq!(sut.delay_for(40, None), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn delay_with_jitter_stays_in_range() {
+        let sut = policy(10, 0.5);
+        for _ in 0..100 {
+            let delay = sut.delay_for(2, None);
+            assert!(delay >= Duration::from_millis(100));
+            assert!(delay <= Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn non_finite_jitter_is_ignored() {
+        for jitter in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            let sut = policy(10, jitter);
+            assert_eq!(sut.delay_for(2, None), Duration::from_millis(200));
+        }
+    }
+
+    #[test]
+    fn delay_honours_retry_after() {
+        let sut = policy(10, 0.5);
+        assert_eq!(
+            sut.delay_for(1, Some(Duration::from_millis(700))),
+            Duration::from_millis(700)
+        );
+        assert_eq!(
+            sut.delay_for(1, Some(Duration::from_secs(60))),
+            Duration::from_millis(1000)
+        );
+    }
+
+    #[test]
+    fn parse_retry_after_seconds_and_date() {
+        let now = Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap();
+        assert_eq!(
+            parse_retry_after(" 120 ", now),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 15 Jan 2025 10:30:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            parse_retry_after("Wed, 15 Jan 2025 10:00:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn retries_retryable_failures_until_exhausted() {
+        let mut attempts = 0;
+        let mut delays = Vec::new();
+        let result = with_retries(
+            policy(3, 0.0),
+            || {
+                attempts += 1;
+                async { Err(status(503)) }
+            },
+            |delay| {
+                delays.push(delay);
+                async {}
+            },
+        )
+        .await;
+
+        assert!(matches!(
+            result,
+            Err(TelemetryDeckError::HttpStatus { status: 503, .. })
+        ));
+        assert_eq!(attempts, 3);
+        assert_eq!(
+            delays,
+            vec![Duration::from_millis(100), Duration::from_millis(200)]
+        );
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_permanent_failures() {
+        let mut attempts = 0;
+        let result = with_retries(
+            policy(3, 0.0),
+            || {
+                attempts += 1;
+                async { Err(status(400)) }
+            },
+            |_| async {},
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+    }
+
+    #[tokio::test]
+    async fn stops_after_success() {
+        let mut attempts = 0;
+        let result = with_retries(
+            policy(5, 0.0),
+            || {
+                attempts += 1;
+                let outcome = if attempts < 2 {
+                    Err(TelemetryDeckError::Transport(
+                        "connection refused".to_string(),
+                    ))
+                } else {
+                    Ok(())
+                };
+                async move { outcome }
+            },
+            |_| async {},
+        )
+        .await;
+
+        assert!(result.is_ok());
+        assert_eq!(attempts, 2);
+    }
+}
@@ -0,0 +1,760 @@
+//! Serde serializer turning any `Serialize` value into payload parameters
+
+use crate::error::TelemetryDeckError;
+use crate::payload::PayloadValue;
+use serde::ser::{self, Error as _, Impossible, Serialize};
+use std::collections::HashMap;
+
+type Error = serde_json::Error;
+
+/// Convert any `Serialize` value into payload parameters
+///
+/// The value must serialize as a struct or a map. Its fields become payload keys and
+/// scalar values are encoded with the same rules as [`PayloadValue`]:
+///
+/// - nested structs and maps are flattened into dotted keys (`device.os`)
+/// - unit enum variants become their variant name; variants carrying data also store
+///   their content under `key.VariantName`, mirroring serde's externally tagged form
+/// - `Option::None` and `()` fields are omitted
+/// - sequences of scalars are encoded comma-separated; sequences of structs are
+///   flattened with their index (`items.0.name`)
+///
+/// The result can be passed as the payload of [`TelemetryDeck::send`](crate::TelemetryDeck::send)
+/// or added to a [`SignalBuilder`](crate::SignalBuilder) with `params_from`.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Serialize;
+/// use telemetrydeck_wasm::payload::to_payload;
+///
+/// #[derive(Serialize)]
+/// enum Plan {
+///     Free,
+///     Pro,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct Screen {
+///     width: u32,
+///     height: u32,
+/// }
+///
+/// #[derive(Serialize)]
+/// struct AppOpened {
+///     plan: Plan,
+///     screen: Screen,
+///     referrer: Option<String>,
+///     tags: Vec<&'static str>,
+/// }
+///
+/// let payload = to_payload(&AppOpened {
+///     plan: Plan::Pro,
+///     screen: Screen { width: 1280, height: 800 },
+///     referrer: None,
+///     tags: vec!["beta", "dark"],
+/// })?;
+///
+/// assert_eq!(payload["plan"], "Pro");
+/// assert_eq!(payload["screen.width"], "1280");
+/// assert_eq!(payload["tags"], "beta,dark");
+/// assert!(!payload.contains_key("referrer"));
+/// # Ok::<(), telemetrydeck_wasm::TelemetryDeckError>(())
+/// ```
+pub fn to_payload<T>(value: &T) -> Result<HashMap<String, String>, TelemetryDeckError>
+where
+    T: Serialize + ?Sized,
+{
+    let mut out = HashMap::new();
+    value.serialize(PayloadSerializer {
+        out: &mut out,
+        key: None,
+    })?;
+    Ok(out)
+}
+
+fn join(prefix: Option<&str>, name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}.{}", prefix, name),
+        None => name.to_string(),
+    }
+}
+
+fn not_a_struct() -> Error {
+    Error::custom("payload must be a struct or a map")
+}
+
+/// Writes the value at `key` into `out`, flattening compound values
+struct PayloadSerializer<'a> {
+    out: &'a mut HashMap<String, String>,
+    key: Option<String>,
+}
+
+impl<'a> PayloadSerializer<'a> {
+    fn scalar(self, value: Option<String>) -> Result<(), Error> {
+        match (self.key, value) {
+            (Some(key), Some(value)) => {
+                self.out.insert(key, value);
+                Ok(())
+            }
+            (Some(_), None) => Ok(()),
+            (None, _) => Err(not_a_struct()),
+        }
+    }
+
+    fn nested(
+        self,
+        variant: &'static str,
+    ) -> Result<(String, &'a mut HashMap<String, String>), Error> {
+        let key = self.key.ok_or_else(not_a_struct)?;
+        self.out.insert(key.clone(), variant.to_string());
+        Ok((join(Some(&key), variant), self.out))
+    }
+}
+
+macro_rules! scalar_methods {
+    ($($method:ident($t:ty)),*) => {
+        $(
+            fn $method(self, v: $t) -> Result<(), Error> {
+                let value = ScalarSerializer.$method(v)?;
+                self.scalar(value)
+            }
+        )*
+    };
+}
+
+impl<'a> ser::Serializer for PayloadSerializer<'a> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = SeqSerializer<'a>;
+    type SerializeTuple = SeqSerializer<'a>;
+    type SerializeTupleStruct = SeqSerializer<'a>;
+    type SerializeTupleVariant = SeqSerializer<'a>;
+    type SerializeMap = MapSerializer<'a>;
+    type SerializeStruct = StructSerializer<'a>;
+    type SerializeStructVariant = StructSerializer<'a>;
+
+    scalar_methods!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_bytes(&[u8])
+    );
+
+    fn serialize_none(self) -> Result<(), Error> {
+        self.scalar(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), Error> {
+        self.scalar(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), Error> {
+        self.scalar(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<(), Error> {
+        self.scalar(Some(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        let (key, out) = self.nested(variant)?;
+        value.serialize(PayloadSerializer {
+            out,
+            key: Some(key),
+        })
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<SeqSerializer<'a>, Error> {
+        let key = self.key.ok_or_else(not_a_struct)?;
+        Ok(SeqSerializer::new(self.out, key))
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<SeqSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<SeqSerializer<'a>, Error> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<SeqSerializer<'a>, Error> {
+        let (key, out) = self.nested(variant)?;
+        Ok(SeqSerializer::new(out, key))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<MapSerializer<'a>, Error> {
+        Ok(MapSerializer {
+            out: self.out,
+            prefix: self.key,
+            key: None,
+        })
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<StructSerializer<'a>, Error> {
+        Ok(StructSerializer {
+            out: self.out,
+            prefix: self.key,
+        })
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<StructSerializer<'a>, Error> {
+        let (key, out) = self.nested(variant)?;
+        Ok(StructSerializer {
+            out,
+            prefix: Some(key),
+        })
+    }
+}
+
+struct StructSerializer<'a> {
+    out: &'a mut HashMap<String, String>,
+    prefix: Option<String>,
+}
+
+impl StructSerializer<'_> {
+    fn field<T: Serialize + ?Sized>(&mut self, name: &str, value: &T) -> Result<(), Error> {
+        value.serialize(PayloadSerializer {
+            out: self.out,
+            key: Some(join(self.prefix.as_deref(), name)),
+        })
+    }
+}
+
+impl ser::SerializeStruct for StructSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+impl ser::SerializeStructVariant for StructSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), Error> {
+        self.field(key, value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+struct MapSerializer<'a> {
+    out: &'a mut HashMap<String, String>,
+    prefix: Option<String>,
+    key: Option<String>,
+}
+
+impl ser::SerializeMap for MapSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), Error> {
+        let key = key
+            .serialize(ScalarSerializer)
+            .ok()
+            .flatten()
+            .ok_or_else(|| Error::custom("payload map keys must be strings or numbers"))?;
+        self.key = Some(key);
+        Ok(())
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let key = self
+            .key
+            .take()
+            .ok_or_else(|| Error::custom("map value serialized before its key"))?;
+        value.serialize(PayloadSerializer {
+            out: self.out,
+            key: Some(join(self.prefix.as_deref(), &key)),
+        })
+    }
+
+    fn end(self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+struct SeqSerializer<'a> {
+    out: &'a mut HashMap<String, String>,
+    key: String,
+    index: usize,
+    scalars: Vec<(usize, String)>,
+    nested: bool,
+}
+
+impl<'a> SeqSerializer<'a> {
+    fn new(out: &'a mut HashMap<String, String>, key: String) -> Self {
+        SeqSerializer {
+            out,
+            key,
+            index: 0,
+            scalars: Vec::new(),
+            nested: false,
+        }
+    }
+
+    fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        let index = self.index;
+        self.index += 1;
+        match value.serialize(ScalarSerializer) {
+            Ok(Some(scalar)) => self.scalars.push((index, scalar)),
+            Ok(None) => {}
+            Err(_) => {
+                self.nested = true;
+                value.serialize(PayloadSerializer {
+                    out: self.out,
+                    key: Some(join(Some(&self.key), &index.to_string())),
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(), Error> {
+        if self.nested {
+            for (index, scalar) in self.scalars {
+                self.out
+                    .insert(join(Some(&self.key), &index.to_string()), scalar);
+            }
+        } else {
+            let values = self.scalars.into_iter().map(|(_, scalar)| scalar).collect();
+            self.out
+                .insert(self.key, PayloadValue::List(values).encode());
+        }
+        Ok(())
+    }
+}
+
+impl ser::SerializeSeq for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTuple for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleStruct for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+impl ser::SerializeTupleVariant for SeqSerializer<'_> {
+    type Ok = ();
+    type Error = Error;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), Error> {
+        self.element(value)
+    }
+
+    fn end(self) -> Result<(), Error> {
+        self.finish()
+    }
+}
+
+/// Encodes a single scalar value, `None` for values that should be omitted
+struct ScalarSerializer;
+
+fn not_a_scalar() -> Error {
+    Error::custom("not a scalar value")
+}
+
+impl ser::Serializer for ScalarSerializer {
+    type Ok = Option<String>;
+    type Error = Error;
+    type SerializeSeq = Impossible<Option<String>, Error>;
+    type SerializeTuple = Impossible<Option<String>, Error>;
+    type SerializeTupleStruct = Impossible<Option<String>, Error>;
+    type SerializeTupleVariant = Impossible<Option<String>, Error>;
+    type SerializeMap = Impossible<Option<String>, Error>;
+    type SerializeStruct = Impossible<Option<String>, Error>;
+    type SerializeStructVariant = Impossible<Option<String>, Error>;
+
+    fn serialize_bool(self, v: bool) -> Result<Option<String>, Error> {
+        Ok(Some(PayloadValue::Bool(v).encode()))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<Option<String>, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<Option<String>, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<Option<String>, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<Option<String>, Error> {
+        Ok(Some(PayloadValue::Integer(v).encode()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<Option<String>, Error> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<Option<String>, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<Option<String>, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<Option<String>, Error> {
+        self.serialize_i64(i64::from(v))
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<Option<String>, Error> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<Option<String>, Error> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<Option<String>, Error> {
+        self.serialize_f64(f64::from(v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<Option<String>, Error> {
+        Ok(Some(PayloadValue::Float(v).encode()))
+    }
+
+    fn serialize_char(self, v: char) -> Result<Option<String>, Error> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<Option<String>, Error> {
+        Ok(Some(v.to_string()))
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<Option<String>, Error> {
+        Err(Error::custom("byte arrays are not supported in payloads"))
+    }
+
+    fn serialize_none(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Option<String>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<Option<String>, Error> {
+        Ok(None)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        variant: &'static str,
+    ) -> Result<Option<String>, Error> {
+        Ok(Some(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<Option<String>, Error> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> Result<Option<String>, Error> {
+        Err(not_a_scalar())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, Error> {
+        Err(not_a_scalar())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, Error> {
+        Err(not_a_scalar())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, Error> {
+        Err(not_a_scalar())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, Error> {
+        Err(not_a_scalar())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Error> {
+        Err(not_a_scalar())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, Error> {
+        Err(not_a_scalar())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, Error> {
+        Err(not_a_scalar())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_payload;
+    use crate::TelemetryDeckError;
+    use serde::Serialize;
+    use std::collections::{BTreeMap, HashMap};
+
+    fn entries(payload: HashMap<String, String>) -> BTreeMap<String, String> {
+        payload.into_iter().collect()
+    }
+
+    fn expected(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[derive(Serialize)]
+    enum Method {
+        Password,
+        Sso { provider: String },
+        Token(u32),
+    }
+
+    #[derive(Serialize)]
+    struct Device {
+        os: &'static str,
+        scale: f64,
+    }
+
+    #[derive(Serialize)]
+    struct Login {
+        method: Method,
+        device: Device,
+        attempts: u8,
+        remember: bool,
+        referrer: Option<String>,
+        scopes: Vec<&'static str>,
+    }
+
+    #[test]
+    fn flattens_nested_structs() {
+        let result = to_payload(&Login {
+            method: Method::Password,
+            device: Device {
+                os: "linux",
+                scale: 2.0,
+            },
+            attempts: 2,
+            remember: true,
+            referrer: None,
+            scopes: vec!["read", "write"],
+        })
+        .unwrap();
+        assert_eq!(
+            entries(result),
+            expected(&[
+                ("attempts", "2"),
+                ("device.os", "linux"),
+                ("device.scale", "2.0"),
+                ("method", "Password"),
+                ("remember", "true"),
+                ("scopes", "read,write"),
+            ])
+        );
+    }
+
+    #[test]
+    fn enum_variants_with_data() {
+        #[derive(Serialize)]
+        struct Event {
+            first: Method,
+            second: Method,
+        }
+
+        let result = to_payload(&Event {
+            first: Method::Sso {
+                provider: "github".to_string(),
+            },
+            second: Method::Token(7),
+        })
+        .unwrap();
+        assert_eq!(
+            entries(result),
+            expected(&[
+                ("first", "Sso"),
+                ("first.Sso.provider", "github"),
+                ("second", "Token"),
+                ("second.Token", "7"),
+            ])
+        );
+    }
+
+    #[test]
+    fn maps_and_sequences_of_structs() {
+        #[derive(Serialize)]
+        struct Item {
+            name: &'static str,
+        }
+
+        #[derive(Serialize)]
+        struct Cart {
+            items: Vec<Item>,
+            totals: BTreeMap<&'static str, u32>,
+        }
+
+        let result = to_payload(&Cart {
+            items: vec![Item { name: "a" }, Item { name: "b" }],
+            totals: BTreeMap::from([("eur", 10), ("usd", 12)]),
+        })
+        .unwrap();
+        assert_eq!(
+            entries(result),
+            expected(&[
+                ("items.0.name", "a"),
+                ("items.1.name", "b"),
+                ("totals.eur", "10"),
+                ("totals.usd", "12"),
+            ])
+        );
+    }
+
+    #[test]
+    fn rejects_values_that_are_not_structs() {
+        assert!(matches!(
+            to_payload(&42),
+            Err(TelemetryDeckError::Serialization(_))
+        ));
+        assert!(matches!(
+            to_payload(&vec!["a"]),
+            Err(TelemetryDeckError::Serialization(_))
+        ));
+    }
+}
@@ -0,0 +1,240 @@
+//! Automatic session lifecycle
+//!
+//! With session tracking enabled, every signal counts as activity. The first one of a
+//! session is preceded by a [`signals::session::STARTED`](crate::signals::session::STARTED)
+//! signal, and a new session (with a new id) starts once the client has been idle for
+//! longer than [`SessionConfig::idle_timeout`]. Signals of a session that follows
+//! another one carry the previous session's duration as
+//! [`params::retention::PREVIOUS_SESSION_SECONDS`](crate::params::retention::PREVIOUS_SESSION_SECONDS).
+
+use crate::core::{SessionState, TelemetryDeck};
+use crate::ids::IdGenerator;
+use chrono::{DateTime, Utc};
+use std::time::Duration;
+
+/// Session tracking configuration
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{SessionConfig, TelemetryDeck};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+/// let client = TelemetryDeck::builder("YOUR-APP-ID")
+///     .session_tracking(SessionConfig {
+///         idle_timeout: Duration::from_secs(10 * 60),
+///     })
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionConfig {
+    /// Time without signals after which the next signal starts a new session
+    ///
+    /// Default: 5 minutes
+    pub idle_timeout: Duration,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        SessionConfig {
+            idle_timeout: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// First and latest activity of the current session
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SessionActivity {
+    started_at: DateTime<Utc>,
+    last_activity_at: DateTime<Utc>,
+}
+
+impl SessionActivity {
+    pub(crate) fn duration(&self) -> Duration {
+        (self.last_activity_at - self.started_at)
+            .to_std()
+            .unwrap_or_default()
+    }
+}
+
+impl TelemetryDeck {
+    /// Time between the first and the latest signal of the current session
+    ///
+    /// `None` without session tracking or before the first signal of a session.
+    #[must_use]
+    pub fn session_duration(&self) -> Option<Duration> {
+        self.read_state()
+            .activity
+            .as_ref()
+            .map(SessionActivity::duration)
+    }
+}
+
+impl SessionState {
+    /// Record activity at `now`, returning whether it starts a new session
+    ///
+    /// A session idle for longer than `idle_timeout` is ended and replaced by one with a
+    /// new id.
+    pub(crate) fn record_activity(
+        &mut self,
+        now: DateTime<Utc>,
+        idle_timeout: Duration,
+        ids: &dyn IdGenerator,
+    ) -> bool {
+        if let Some(activity) = &mut self.activity {
+            let idle = (now - activity.last_activity_at)
+                .to_std()
+                .unwrap_or_default();
+            if idle <= idle_timeout {
+                activity.last_activity_at = activity.last_activity_at.max(now);
+                return false;
+            }
+            self.end_session();
+            self.session_id = ids.session_id();
+        }
+        self.activity = Some(SessionActivity {
+            started_at: now,
+            last_activity_at: now,
+        });
+        true
+    }
+
+    /// End the current session, remembering its duration for the next one
+    pub(crate) fn end_session(&mut self) {
+        if let Some(activity) = self.activity.take() {
+            self.previous_session_seconds = Some(activity.duration().as_secs());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SessionConfig;
+    use crate::{FixedClock, TelemetryDeck, params, signals};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    fn tracked_client() -> (
+        TelemetryDeck,
+        crate::testing::RecordingTransport,
+        FixedClock,
+    ) {
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        let client = client
+            .with_clock(clock.clone())
+            .with_session_tracking(SessionConfig {
+                idle_timeout: Duration::from_secs(60),
+            });
+        (client, recorder, clock)
+    }
+
+    #[tokio::test]
+    async fn first_activity_sends_session_started() {
+        let (client, recorder, clock) = tracked_client();
+        let session_id = client.session_id();
+        assert_eq!(client.session_duration(), None);
+
+        client.send("appOpened", None, None, None, None);
+        clock.advance(Duration::from_secs(30));
+        client.send("settingsOpened", None, None, None, None);
+        client.flush().await.unwrap();
+
+        let types: Vec<_> = recorder
+            .signals()
+            .into_iter()
+            .map(|s| s.signal_type)
+            .collect();
+        assert_eq!(
+            types,
+            vec![signals::session::STARTED, "appOpened", "settingsOpened"]
+        );
+        assert_eq!(client.session_id(), session_id);
+        assert_eq!(client.session_duration(), Some(Duration::from_secs(30)));
+        let started = recorder.assert_signal_sent(signals::session::STARTED);
+        assert_eq!(started.session_id, session_id);
+        assert_eq!(
+            started.param(params::retention::PREVIOUS_SESSION_SECONDS),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_starts_new_session_with_previous_duration() {
+        let (client, recorder, clock) = tracked_client();
+        let first_session = client.session_id();
+        client.send("appOpened", None, None, None, None);
+        clock.advance(Duration::from_secs(45));
+        client.send("settingsOpened", None, None, None, None);
+        clock.advance(Duration::from_secs(61));
+        client.send("appOpened", None, None, None, None);
+        client.flush().await.unwrap();
+
+        assert_ne!(client.session_id(), first_session);
+        assert_eq!(recorder.signals_of_type(signals::session::STARTED).len(), 2);
+        let signal = recorder.assert_signal_sent_with(
+            "appOpened",
+            &[(params::retention::PREVIOUS_SESSION_SECONDS, "45")],
+        );
+        assert_eq!(signal.session_id, client.session_id());
+        assert_eq!(client.session_duration(), Some(Duration::ZERO));
+    }
+
+    #[tokio::test]
+    async fn reset_session_ends_tracked_session() {
+        let (client, recorder, clock) = tracked_client();
+        client.send("appOpened", None, None, None, None);
+        clock.advance(Duration::from_secs(10));
+        client.send("appOpened", None, None, None, None);
+        client.reset_session(Some("second".to_string()));
+        client.send("appOpened", None, None, None, None);
+        client.flush().await.unwrap();
+
+        let started = recorder.signals_of_type(signals::session::STARTED);
+        assert_eq!(started.len(), 2);
+        assert_eq!(started[1].session_id, "second");
+        assert_eq!(
+            started[1].param(params::retention::PREVIOUS_SESSION_SECONDS),
+            Some("10")
+        );
+    }
+
+    #[tokio::test]
+    async fn session_started_has_user_and_test_mode_of_first_signal() {
+        let (client, recorder, _clock) = tracked_client();
+        client
+            .signal("appOpened")
+            .user("alice")
+            .test_mode(true)
+            .send();
+        client.flush().await.unwrap();
+
+        let opened = recorder.assert_signal_sent("appOpened");
+        let started = recorder.assert_signal_sent(signals::session::STARTED);
+        assert_eq!(started.client_user, opened.client_user);
+        assert_eq!(started.is_test_mode, "true");
+    }
+
+    // Runs outside a tokio runtime: building must not queue anything
+    #[test]
+    fn building_a_signal_is_not_session_activity() {
+        let (client, recorder, _clock) = tracked_client();
+        let signal = client.signal("appOpened").build();
+
+        assert_eq!(signal.session_id, client.session_id());
+        assert_eq!(client.session_duration(), None);
+        assert!(recorder.signals().is_empty());
+    }
+
+    #[tokio::test]
+    async fn untracked_client_sends_no_session_signals() {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        client.send("appOpened", None, None, None, None);
+        client.flush().await.unwrap();
+        recorder.assert_signal_not_sent(signals::session::STARTED);
+        assert_eq!(client.session_duration(), None);
+    }
+}
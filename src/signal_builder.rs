@@ -0,0 +1,248 @@
+use crate::core::{Signal, TelemetryDeck};
+use crate::error::TelemetryDeckError;
+use crate::payload::{PayloadValue, to_payload};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Fluent builder for a single outgoing signal
+///
+/// Created with [`TelemetryDeck::signal`]. Only the signal type is required; everything
+/// else falls back to the client's defaults. Finish with [`send`](Self::send)
+/// (fire-and-forget) or [`send_and_wait`](Self::send_and_wait).
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::TelemetryDeck;
+///
+/// # async fn example() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+///
+/// client
+///     .signal("userLogin")
+///     .user("user@example.com")
+///     .param("method", "password")
+///     .send();
+///
+/// client
+///     .signal("exportFinished")
+///     .float(1.5)
+///     .test_mode(true)
+///     .send_and_wait()
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+#[must_use = "a signal is only sent once `send` or `send_and_wait` is called"]
+pub struct SignalBuilder<'a> {
+    client: &'a TelemetryDeck,
+    signal_type: String,
+    user: Option<String>,
+    params: HashMap<String, String>,
+    is_test_mode: Option<bool>,
+    float_value: Option<f64>,
+}
+
+impl TelemetryDeck {
+    /// Start building a signal of the specified type
+    ///
+    /// See [`SignalBuilder`] for the available settings.
+    pub fn signal(&self, signal_type: &str) -> SignalBuilder<'_> {
+        SignalBuilder {
+            client: self,
+            signal_type: signal_type.to_string(),
+            user: None,
+            params: HashMap::new(),
+            is_test_mode: None,
+            float_value: None,
+        }
+    }
+}
+
+impl SignalBuilder<'_> {
+    /// User identifier, SHA-256 hashed before it is sent
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Add a payload parameter
+    ///
+    /// The value is encoded with [`PayloadValue::encode`]. Overrides a default parameter
+    /// with the same key.
+    pub fn param(mut self, key: impl Into<String>, value: impl Into<PayloadValue>) -> Self {
+        self.params.insert(key.into(), value.into().encode());
+        self
+    }
+
+    /// Add several payload parameters
+    pub fn params<K, V>(mut self, params: impl IntoIterator<Item = (K, V)>) -> Self
+    where
+        K: Into<String>,
+        V: Into<PayloadValue>,
+    {
+        self.params.extend(
+            params
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into().encode())),
+        );
+        self
+    }
+
+    /// Add the fields of a `Serialize` value as payload parameters
+    ///
+    /// See [`to_payload`] for how values are flattened. Fails if the value does not
+    /// serialize as a struct or map.
+    pub fn params_from<T: Serialize + ?Sized>(
+        mut self,
+        value: &T,
+    ) -> Result<Self, TelemetryDeckError> {
+        self.params.extend(to_payload(value)?);
+        Ok(self)
+    }
+
+    /// Floating-point value associated with the signal
+    pub fn float(mut self, value: f64) -> Self {
+        self.float_value = Some(value);
+        self
+    }
+
+    /// Whether this is a test signal, overriding the client's default
+    pub fn test_mode(mut self, is_test_mode: bool) -> Self {
+        self.is_test_mode = Some(is_test_mode);
+        self
+    }
+
+    /// Create the signal without sending it
+    ///
+    /// Unlike sending, building does not count as session activity, so it never starts
+    /// a session or queues an announcement.
+    pub fn build(self) -> Signal {
+        let payload = (!self.params.is_empty()).then_some(self.params);
+        self.client.create_signal(
+            &self.signal_type,
+            self.user.as_deref(),
+            payload,
+            self.is_test_mode,
+            self.float_value,
+        )
+    }
+
+    /// Queue the signal for delivery (fire-and-forget)
+    ///
+    /// Behaves like [`TelemetryDeck::send`].
+    pub fn send(self) {
+        let client = self.client;
+        client.send_one(self.build_outgoing());
+    }
+
+    /// Send the signal right away and wait for the result
+    ///
+    /// Behaves like [`TelemetryDeck::send_sync`].
+    pub async fn send_and_wait(self) -> Result<(), TelemetryDeckError> {
+        let client = self.client;
+        client.send_many_sync(vec![self.build_outgoing()]).await
+    }
+
+    /// Create the signal as part of sending it, see [`TelemetryDeck::create_outgoing_signal`]
+    fn build_outgoing(self) -> Signal {
+        let payload = (!self.params.is_empty()).then_some(self.params);
+        self.client.create_outgoing_signal(
+            &self.signal_type,
+            self.user.as_deref(),
+            payload,
+            self.is_test_mode,
+            self.float_value,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TelemetryDeck;
+    const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+    #[test]
+    fn build_with_defaults() {
+        let client = TelemetryDeck::new("1234");
+        let result = client.signal("signal_type").build();
+        assert_eq!(result.signal_type, "signal_type");
+        assert_eq!(result.client_user, "rust");
+        assert_eq!(result.is_test_mode, "false");
+        assert_eq!(
+            result.payload,
+            vec![format!("telemetryClientVersion:{VERSION}")]
+        );
+        assert_eq!(result.float_value, None);
+    }
+
+    #[test]
+    fn build_with_all_settings() {
+        let client = TelemetryDeck::new("1234");
+        let result = client
+            .signal("signal_type")
+            .user("clientUser")
+            .param("key", "value")
+            .float(1.5)
+            .test_mode(true)
+            .build();
+        assert_eq!(
+            result.client_user,
+            "6721870580401922549fe8fdb09a064dba5b8792fa018d3bd9ffa90fe37a0149"
+        );
+        assert!(result.payload.contains(&"key:value".to_string()));
+        assert_eq!(result.float_value, Some(1.5));
+        assert_eq!(result.is_test_mode, "true");
+    }
+
+    #[test]
+    fn params_override_defaults() {
+        let client = TelemetryDeck::builder("1234")
+            .default_param("screen", "home")
+            .build()
+            .unwrap();
+        let result = client
+            .signal("signal_type")
+            .params([("screen", "settings")])
+            .build();
+        assert!(result.payload.contains(&"screen:settings".to_string()));
+        assert!(!result.payload.contains(&"screen:home".to_string()));
+    }
+
+    #[test]
+    fn typed_params_are_encoded() {
+        let client = TelemetryDeck::new("1234");
+        let result = client
+            .signal("signal_type")
+            .param("count", 3)
+            .param("ratio", 2.0)
+            .param("enabled", true)
+            .param("formats", vec!["pdf", "png"])
+            .build();
+        for expected in ["count:3", "ratio:2.0", "enabled:true", "formats:pdf,png"] {
+            assert!(result.payload.contains(&expected.to_string()), "{expected}");
+        }
+    }
+
+    #[test]
+    fn params_from_serialized_struct() {
+        #[derive(serde::Serialize)]
+        struct Checkout {
+            items: u32,
+            coupon: Option<String>,
+        }
+
+        let client = TelemetryDeck::new("1234");
+        let result = client
+            .signal("signal_type")
+            .params_from(&Checkout {
+                items: 3,
+                coupon: None,
+            })
+            .unwrap()
+            .build();
+        assert!(result.payload.contains(&"items:3".to_string()));
+        assert!(!result.payload.iter().any(|p| p.starts_with("coupon")));
+    }
+}
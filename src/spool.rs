@@ -0,0 +1,468 @@
+//! Offline spool for undelivered signals (native only)
+//!
+//! When a batch of fire-and-forget signals cannot be delivered because of a retryable
+//! failure (no network, timeout, `5xx`...), it is written to the spool directory instead
+//! of being lost. Spooled signals are sent again after the next successful flush, and when
+//! a client with the same spool directory is created, e.g. on the next start of the app.
+//!
+//! Every failed batch is stored as its own segment, a JSONL file with one serialized
+//! [`Signal`] per line, so signals keep their original `receivedAt` timestamp. Segments
+//! are written to a temporary file first and renamed once complete, so a crash never
+//! leaves a partial segment behind. Only one replay runs at a time, and a segment is
+//! removed once it was delivered or permanently rejected (for example with a `4xx`).
+//! Delivery is at least once: a crash between delivering a segment and removing it
+//! sends that segment again on the next replay.
+
+use crate::core::Signal;
+use crate::error::TelemetryDeckError;
+use chrono::Utc;
+use std::fs;
+use std::future::Future;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SEGMENT_EXTENSION: &str = "jsonl";
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Configuration of the offline spool
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{SpoolConfig, TelemetryDeck};
+/// use std::time::Duration;
+///
+/// # fn main() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+/// let client = TelemetryDeck::builder("YOUR-APP-ID")
+///     .spool(SpoolConfig {
+///         max_age: Duration::from_secs(24 * 60 * 60),
+///         ..SpoolConfig::new("/var/lib/my-app/telemetry")
+///     })
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpoolConfig {
+    /// Directory holding the spooled signals, created if missing
+    ///
+    /// The directory should not be shared by several running clients.
+    pub directory: PathBuf,
+
+    /// Maximum total size of the spool on disk
+    ///
+    /// The oldest spooled signals are removed to make room for new ones.
+    /// Default: 10 MiB
+    pub max_size_bytes: u64,
+
+    /// Maximum age of a spooled signal
+    ///
+    /// Older signals are discarded instead of being sent.
+    /// Default: 7 days
+    pub max_age: Duration,
+}
+
+impl SpoolConfig {
+    /// Spool in the specified directory with the default limits
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        SpoolConfig {
+            directory: directory.into(),
+            max_size_bytes: 10 * 1024 * 1024,
+            max_age: Duration::from_secs(7 * 24 * 60 * 60),
+        }
+    }
+}
+
+/// File-backed store of signals waiting to be delivered again
+#[derive(Debug)]
+pub(crate) struct Spool {
+    config: SpoolConfig,
+    /// Held while segment files are listed, written or removed
+    files: Mutex<()>,
+    /// Held for the whole duration of a replay so that spooled signals are sent once
+    replaying: futures::lock::Mutex<()>,
+}
+
+impl Spool {
+    /// Open the spool, creating its directory and removing unfinished segments
+    pub(crate) fn open(config: SpoolConfig) -> io::Result<Self> {
+        fs::create_dir_all(&config.directory)?;
+        for entry in fs::read_dir(&config.directory)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == TEMP_EXTENSION) {
+                remove_if_exists(&path)?;
+            }
+        }
+        Ok(Spool {
+            config,
+            files: Mutex::new(()),
+            replaying: futures::lock::Mutex::new(()),
+        })
+    }
+
+    /// Whether there are no spooled signals
+    pub(crate) fn is_empty(&self) -> bool {
+        let _files = self.lock_files();
+        self.segments().map_or(true, |segments| segments.is_empty())
+    }
+
+    /// Store signals as a new segment, evicting the oldest segments if the spool is full
+    ///
+    /// A batch larger than the whole spool is not stored.
+    pub(crate) fn store(&self, signals: &[Signal]) -> Result<(), TelemetryDeckError> {
+        let mut contents = String::new();
+        for signal in signals {
+            contents.push_str(&serde_json::to_string(signal)?);
+            contents.push('\n');
+        }
+        let size = contents.len() as u64;
+        if signals.is_empty() || size > self.config.max_size_bytes {
+            return Ok(());
+        }
+
+        let _files = self.lock_files();
+        let segments = self.segments()?;
+        let mut total: u64 = segments.iter().map(|(_, len)| len).sum();
+        for (path, len) in segments {
+            if total + size <= self.config.max_size_bytes {
+                break;
+            }
+            remove_if_exists(&path)?;
+            total -= len;
+        }
+
+        let name = segment_name();
+        let temp = self
+            .config
+            .directory
+            .join(format!("{name}.{TEMP_EXTENSION}"));
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(
+            &temp,
+            self.config
+                .directory
+                .join(format!("{name}.{SEGMENT_EXTENSION}")),
+        )?;
+        Ok(())
+    }
+
+    /// Deliver spooled signals, oldest segment first
+    ///
+    /// A segment is removed once `deliver` succeeded for it, or failed with an error that
+    /// is not [retryable](TelemetryDeckError::is_retryable), since sending it again would
+    /// fail the same way. Replay stops at the first retryable failure, keeping that
+    /// segment and the newer ones. The first permanent failure is returned once the
+    /// other segments were delivered. Returns right away if another replay is in
+    /// progress.
+    pub(crate) async fn replay<F, Fut>(&self, mut deliver: F) -> Result<(), TelemetryDeckError>
+    where
+        F: FnMut(Vec<Signal>) -> Fut,
+        Fut: Future<Output = Result<(), TelemetryDeckError>>,
+    {
+        let Some(_replaying) = self.replaying.try_lock() else {
+            return Ok(());
+        };
+        let segments = {
+            let _files = self.lock_files();
+            self.segments()?
+        };
+        let mut rejected = None;
+        for (path, _) in segments {
+            let signals = {
+                let _files = self.lock_files();
+                self.read(&path)?
+            };
+            // Evicted to make room for newer signals since the replay started
+            let Some(signals) = signals else {
+                continue;
+            };
+            if !signals.is_empty() {
+                match deliver(signals).await {
+                    Ok(()) => {}
+                    Err(e) if e.is_retryable() => return Err(e),
+                    Err(e) => {
+                        rejected.get_or_insert(e);
+                    }
+                }
+            }
+            let _files = self.lock_files();
+            remove_if_exists(&path)?;
+        }
+        rejected.map_or(Ok(()), Err)
+    }
+
+    fn lock_files(&self) -> MutexGuard<'_, ()> {
+        self.files.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Complete segments and their size, oldest first
+    fn segments(&self) -> io::Result<Vec<(PathBuf, u64)>> {
+        let mut segments = Vec::new();
+        for entry in fs::read_dir(&self.config.directory)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == SEGMENT_EXTENSION) {
+                segments.push((path, entry.metadata()?.len()));
+            }
+        }
+        segments.sort();
+        Ok(segments)
+    }
+
+    /// Signals of a segment that are still recent enough, `None` if it no longer exists
+    ///
+    /// Lines that cannot be parsed are skipped.
+    fn read(&self, path: &Path) -> io::Result<Option<Vec<Signal>>> {
+        let file = match fs::File::open(path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let cutoff = chrono::Duration::from_std(self.config.max_age)
+            .ok()
+            .and_then(|max_age| Utc::now().checked_sub_signed(max_age));
+        let mut signals = Vec::new();
+        for line in BufReader::new(file).lines() {
+            let Ok(signal) = serde_json::from_str::<Signal>(&line?) else {
+                continue;
+            };
+            if cutoff.is_none_or(|cutoff| signal.received_at >= cutoff) {
+                signals.push(signal);
+            }
+        }
+        Ok(Some(signals))
+    }
+}
+
+/// File name of a new segment, sorting after every existing one
+fn segment_name() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{:020}-{:08x}", nanos, fastrand::u32(..))
+}
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Spool, SpoolConfig};
+    use crate::core::Signal;
+    use crate::{RetryPolicy, TelemetryDeck, TelemetryDeckError};
+    use chrono::Utc;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn directory() -> PathBuf {
+        std::env::temp_dir().join(format!("telemetrydeck-spool-{:016x}", fastrand::u64(..)))
+    }
+
+    fn signals(names: &[&str]) -> Vec<Signal> {
+        let client = TelemetryDeck::new("1234");
+        names
+            .iter()
+            .map(|name| client.create_signal(name, None, None, None, None))
+            .collect()
+    }
+
+    fn types(signals: &[Signal]) -> Vec<String> {
+        signals.iter().map(|s| s.signal_type.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn replay_delivers_oldest_first_and_keeps_timestamps() {
+        let directory = directory();
+        let sut = Spool::open(SpoolConfig::new(&directory)).unwrap();
+        let first = signals(&["a", "b"]);
+        let received_at = first[0].received_at;
+        sut.store(&first).unwrap();
+        sut.store(&signals(&["c"])).unwrap();
+        assert!(!sut.is_empty());
+
+        let delivered = Mutex::new(Vec::new());
+        sut.replay(|batch| {
+            delivered.lock().unwrap().push(batch);
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+
+        let delivered = delivered.into_inner().unwrap();
+        assert_eq!(delivered.len(), 2);
+        assert_eq!(types(&delivered[0]), ["a", "b"]);
+        assert_eq!(types(&delivered[1]), ["c"]);
+        assert_eq!(delivered[0][0].received_at, received_at);
+        assert!(sut.is_empty());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_replay_keeps_segments_without_duplicates() {
+        let directory = directory();
+        let sut = Spool::open(SpoolConfig::new(&directory)).unwrap();
+        sut.store(&signals(&["a"])).unwrap();
+        sut.store(&signals(&["b"])).unwrap();
+
+        let result = sut
+            .replay(|_| async { Err(TelemetryDeckError::Transport("offline".to_string())) })
+            .await;
+        assert!(matches!(result, Err(TelemetryDeckError::Transport(_))));
+
+        // Reopening, as on the next start of the app, finds the same signals
+        let sut = Spool::open(SpoolConfig::new(&directory)).unwrap();
+        let delivered = Mutex::new(Vec::new());
+        sut.replay(|batch| {
+            delivered.lock().unwrap().extend(types(&batch));
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(delivered.into_inner().unwrap(), ["a", "b"]);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejected_segment_does_not_block_newer_ones() {
+        let directory = directory();
+        let sut = Spool::open(SpoolConfig::new(&directory)).unwrap();
+        sut.store(&signals(&["rejected"])).unwrap();
+        sut.store(&signals(&["accepted"])).unwrap();
+
+        let delivered = Mutex::new(Vec::new());
+        let result = sut
+            .replay(|batch| {
+                let rejected = batch[0].signal_type == "rejected";
+                if !rejected {
+                    delivered.lock().unwrap().extend(types(&batch));
+                }
+                async move {
+                    if rejected {
+                        Err(TelemetryDeckError::HttpStatus {
+                            status: 400,
+                            body: String::new(),
+                            retry_after: None,
+                        })
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+        assert!(matches!(
+            result,
+            Err(TelemetryDeckError::HttpStatus { status: 400, .. })
+        ));
+        assert_eq!(delivered.into_inner().unwrap(), ["accepted"]);
+        assert!(sut.is_empty());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn full_spool_evicts_oldest_segments() {
+        let directory = directory();
+        let one = signals(&["a"]);
+        let segment_size = serde_json::to_string(&one[0]).unwrap().len() as u64 + 1;
+        let sut = Spool::open(SpoolConfig {
+            max_size_bytes: segment_size * 2,
+            ..SpoolConfig::new(&directory)
+        })
+        .unwrap();
+        for name in ["a", "b", "c"] {
+            sut.store(&signals(&[name])).unwrap();
+        }
+
+        let delivered = Mutex::new(Vec::new());
+        sut.replay(|batch| {
+            delivered.lock().unwrap().extend(types(&batch));
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(delivered.into_inner().unwrap(), ["b", "c"]);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn replay_skips_expired_and_malformed_entries() {
+        let directory = directory();
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("0-torn.jsonl"), "{\"receivedAt\":").unwrap();
+        fs::write(directory.join("1-unfinished.tmp"), "garbage").unwrap();
+
+        // Unfinished segments are removed when the spool is opened
+        let sut = Spool::open(SpoolConfig {
+            max_age: Duration::from_secs(60),
+            ..SpoolConfig::new(&directory)
+        })
+        .unwrap();
+        assert!(!directory.join("1-unfinished.tmp").exists());
+
+        let mut batch = signals(&["old", "new"]);
+        batch[0].received_at = Utc::now() - chrono::Duration::minutes(5);
+        sut.store(&batch).unwrap();
+
+        let delivered = Mutex::new(Vec::new());
+        sut.replay(|batch| {
+            delivered.lock().unwrap().extend(types(&batch));
+            async { Ok(()) }
+        })
+        .await
+        .unwrap();
+        assert_eq!(delivered.into_inner().unwrap(), ["new"]);
+        assert!(sut.is_empty());
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[tokio::test]
+    async fn failed_flush_spools_signals() {
+        let directory = directory();
+        let client = TelemetryDeck::builder("1234")
+            .url("http://127.0.0.1:1")
+            .retry_policy(RetryPolicy::none())
+            .spool(SpoolConfig::new(&directory))
+            .build()
+            .unwrap();
+        client.send("offline", None, None, None, None);
+
+        let result = client.flush().await;
+        assert!(matches!(result, Err(TelemetryDeckError::Transport(_))));
+
+        let spool = Spool::open(SpoolConfig::new(&directory)).unwrap();
+        let delivered = Mutex::new(Vec::new());
+        spool
+            .replay(|batch| {
+                delivered.lock().unwrap().extend(types(&batch));
+                async { Ok(()) }
+            })
+            .await
+            .unwrap();
+        assert_eq!(delivered.into_inner().unwrap(), ["offline"]);
+        fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn build_rejects_empty_spool_limits() {
+        let result = TelemetryDeck::builder("1234")
+            .spool(SpoolConfig {
+                max_size_bytes: 0,
+                ..SpoolConfig::new(directory())
+            })
+            .build();
+        assert!(matches!(
+            result,
+            Err(TelemetryDeckError::InvalidConfiguration(_))
+        ));
+    }
+}
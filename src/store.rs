@@ -0,0 +1,187 @@
+//! Persistent key-value storage for client state kept across runs
+//!
+//! Used by retention tracking to remember past sessions. [`MemoryStore`] keeps values
+//! for the lifetime of the process, which is enough for tests; `FileStore` (native
+//! only) keeps them in a JSON file.
+
+use crate::error::TelemetryDeckError;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex, MutexGuard};
+#[cfg(not(feature = "wasm"))]
+use std::{fs, io::Write, path::PathBuf};
+
+/// Storage of string values by key
+///
+/// Reads and writes happen while signals are created, so implementations should be
+/// fast. Values are small JSON documents.
+///
+/// # Examples
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use std::sync::Mutex;
+/// use telemetrydeck_wasm::{KeyValueStore, TelemetryDeckError};
+///
+/// /// Keeps values in the application's own settings
+/// #[derive(Debug, Default)]
+/// struct SettingsStore {
+///     values: Mutex<HashMap<String, String>>,
+/// }
+///
+/// impl KeyValueStore for SettingsStore {
+///     fn get(&self, key: &str) -> Result<Option<String>, TelemetryDeckError> {
+///         Ok(self.values.lock().unwrap().get(key).cloned())
+///     }
+///
+///     fn set(&self, key: &str, value: String) -> Result<(), TelemetryDeckError> {
+///         self.values.lock().unwrap().insert(key.to_string(), value);
+///         Ok(())
+///     }
+/// }
+/// ```
+pub trait KeyValueStore: fmt::Debug + Send + Sync {
+    /// Value stored for `key`, if any
+    fn get(&self, key: &str) -> Result<Option<String>, TelemetryDeckError>;
+
+    /// Store `value` for `key`, replacing the previous value
+    fn set(&self, key: &str, value: String) -> Result<(), TelemetryDeckError>;
+}
+
+impl<T: KeyValueStore + ?Sized> KeyValueStore for Arc<T> {
+    fn get(&self, key: &str) -> Result<Option<String>, TelemetryDeckError> {
+        (**self).get(key)
+    }
+
+    fn set(&self, key: &str, value: String) -> Result<(), TelemetryDeckError> {
+        (**self).set(key, value)
+    }
+}
+
+/// [`KeyValueStore`] keeping values in memory
+///
+/// Clones share the same values, so a test can keep a clone to inspect them or to
+/// create a second client that sees the history of the first.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    values: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl MemoryStore {
+    /// Empty store
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, String>> {
+        self.values.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl KeyValueStore for MemoryStore {
+    fn get(&self, key: &str) -> Result<Option<String>, TelemetryDeckError> {
+        Ok(self.lock().get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: String) -> Result<(), TelemetryDeckError> {
+        self.lock().insert(key.to_string(), value);
+        Ok(())
+    }
+}
+
+/// [`KeyValueStore`] keeping values in a JSON file (native only)
+///
+/// The file is read once when the store is opened. Every write replaces it atomically
+/// (write to a temporary file, then rename), so a crash never leaves a partial file.
+#[cfg(not(feature = "wasm"))]
+#[derive(Debug)]
+pub struct FileStore {
+    path: PathBuf,
+    values: Mutex<HashMap<String, String>>,
+}
+
+#[cfg(not(feature = "wasm"))]
+impl FileStore {
+    /// Open the store at `path`, creating its parent directory if needed
+    ///
+    /// A missing file is an empty store; a file that is not a JSON object of strings is
+    /// ignored and replaced by the next write.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`TelemetryDeckError::Io`] if the directory cannot be created or the file
+    /// cannot be read.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, TelemetryDeckError> {
+        let path = path.into();
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let values = match fs::read_to_string(&path) {
+            Ok(contents) => serde_json::from_str(&contents).unwrap_or_default(),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(FileStore {
+            path,
+            values: Mutex::new(values),
+        })
+    }
+}
+
+#[cfg(not(feature = "wasm"))]
+impl KeyValueStore for FileStore {
+    fn get(&self, key: &str) -> Result<Option<String>, TelemetryDeckError> {
+        let values = self.values.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(values.get(key).cloned())
+    }
+
+    fn set(&self, key: &str, value: String) -> Result<(), TelemetryDeckError> {
+        let mut values = self.values.lock().unwrap_or_else(|e| e.into_inner());
+        values.insert(key.to_string(), value);
+        let contents = serde_json::to_string(&*values)?;
+
+        let mut temp = self.path.clone().into_os_string();
+        temp.push(".tmp");
+        let mut file = fs::File::create(&temp)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&temp, &self.path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyValueStore, MemoryStore};
+
+    #[test]
+    fn memory_store_clones_share_values() {
+        let sut = MemoryStore::new();
+        let clone = sut.clone();
+        assert_eq!(sut.get("key").unwrap(), None);
+        clone.set("key", "value".to_string()).unwrap();
+        assert_eq!(sut.get("key").unwrap(), Some("value".to_string()));
+    }
+
+    #[cfg(not(feature = "wasm"))]
+    #[test]
+    fn file_store_persists_values() {
+        use super::FileStore;
+        use std::fs;
+
+        let directory =
+            std::env::temp_dir().join(format!("telemetrydeck-store-{:016x}", fastrand::u64(..)));
+        let path = directory.join("state.json");
+        let sut = FileStore::open(&path).unwrap();
+        assert_eq!(sut.get("key").unwrap(), None);
+        sut.set("key", "value".to_string()).unwrap();
+
+        let reopened = FileStore::open(&path).unwrap();
+        assert_eq!(reopened.get("key").unwrap(), Some("value".to_string()));
+
+        fs::write(&path, "not json").unwrap();
+        assert_eq!(FileStore::open(&path).unwrap().get("key").unwrap(), None);
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
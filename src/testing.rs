@@ -0,0 +1,284 @@
+//! Helpers for testing code that sends signals (requires the `testing` feature)
+//!
+//! [`RecordingTransport`] keeps every signal in memory instead of posting it, so tests
+//! can check which signals were emitted without network access.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use telemetrydeck_wasm::TelemetryDeck;
+//!
+//! fn log_in(client: &TelemetryDeck, method: &str) {
+//!     client.signal("userLogin").param("method", method).send();
+//! }
+//!
+//! # async fn example() -> Result<(), telemetrydeck_wasm::TelemetryDeckError> {
+//! let (client, recorder) = TelemetryDeck::new_recording("YOUR-APP-ID");
+//! log_in(&client, "sso");
+//! client.flush().await?;
+//!
+//! recorder.assert_signal_sent_with("userLogin", &[("method", "sso")]);
+//! recorder.assert_signal_not_sent("userLogout");
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::builder::TelemetryDeckBuilder;
+use crate::core::{Signal, TelemetryDeck};
+use crate::queue::BatchConfig;
+use crate::retry::RetryPolicy;
+use crate::transport::{Transport, TransportFuture};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// [`Transport`] recording signals in memory instead of sending them
+///
+/// Clones share the same recorded signals. Signals passed to `send()` are only recorded
+/// once they are flushed, so call [`TelemetryDeck::flush`] before checking them.
+#[derive(Debug, Clone, Default)]
+pub struct RecordingTransport {
+    signals: Arc<Mutex<Vec<Signal>>>,
+}
+
+impl TelemetryDeck {
+    /// Create a client recording its signals with a [`RecordingTransport`]
+    ///
+    /// Deliveries are never retried, and every fire-and-forget signal is flushed on its own.
+    /// No HTTP client is created.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `app_id` is empty.
+    #[must_use]
+    pub fn new_recording(app_id: &str) -> (Self, RecordingTransport) {
+        let (builder, recorder) = Self::recording_builder(app_id);
+        let client = builder
+            .build()
+            .expect("a recording client needs a non-empty app id");
+        (client, recorder)
+    }
+
+    /// Builder configured like [`new_recording`](Self::new_recording), for tests of
+    /// settings only the builder offers
+    pub(crate) fn recording_builder(app_id: &str) -> (TelemetryDeckBuilder, RecordingTransport) {
+        let recorder = RecordingTransport::new();
+        let builder = TelemetryDeck::builder(app_id)
+            .batch_config(recording_batch_config())
+            .retry_policy(RetryPolicy::none())
+            .transport(recorder.clone());
+        (builder, recorder)
+    }
+}
+
+/// Flush every fire-and-forget signal on its own
+fn recording_batch_config() -> BatchConfig {
+    BatchConfig {
+        max_batch_size: 1,
+        flush_interval: Duration::ZERO,
+        ..BatchConfig::default()
+    }
+}
+
+impl RecordingTransport {
+    /// Empty recorder
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Every recorded signal, in the order it was delivered
+    #[must_use]
+    pub fn signals(&self) -> Vec<Signal> {
+        self.lock().clone()
+    }
+
+    /// Recorded signals of the specified type
+    #[must_use]
+    pub fn signals_of_type(&self, signal_type: &str) -> Vec<Signal> {
+        self.lock()
+            .iter()
+            .filter(|signal| signal.signal_type == signal_type)
+            .cloned()
+            .collect()
+    }
+
+    /// Forget every recorded signal
+    pub fn clear(&self) {
+        self.lock().clear();
+    }
+
+    /// Assert that a signal of the specified type was recorded, and return the latest one
+    ///
+    /// # Panics
+    ///
+    /// Panics, listing the recorded signal types, if there is no such signal.
+    #[track_caller]
+    pub fn assert_signal_sent(&self, signal_type: &str) -> Signal {
+        self.assert_signal_sent_with(signal_type, &[])
+    }
+
+    /// Assert that a signal of the specified type with all of `params` in its payload was
+    /// recorded, and return the latest one
+    ///
+    /// # Panics
+    ///
+    /// Panics, listing the recorded signals of that type, if there is no such signal.
+    #[track_caller]
+    pub fn assert_signal_sent_with(&self, signal_type: &str, params: &[(&str, &str)]) -> Signal {
+        let signals = self.signals_of_type(signal_type);
+        if signals.is_empty() {
+            panic!(
+                "expected a `{}` signal, recorded: {:?}",
+                signal_type,
+                self.signal_types()
+            );
+        }
+        signals
+            .iter()
+            .rev()
+            .find(|signal| {
+                params
+                    .iter()
+                    .all(|(key, value)| signal.param(key) == Some(*value))
+            })
+            .cloned()
+            .unwrap_or_else(|| {
+                let payloads: Vec<_> = signals.iter().map(|signal| &signal.payload).collect();
+                panic!(
+                    "expected a `{}` signal with {:?}, recorded payloads: {:?}",
+                    signal_type, params, payloads
+                )
+            })
+    }
+
+    /// Assert that no signal of the specified type was recorded
+    ///
+    /// # Panics
+    ///
+    /// Panics if there is such a signal.
+    #[track_caller]
+    pub fn assert_signal_not_sent(&self, signal_type: &str) {
+        let count = self.signals_of_type(signal_type).len();
+        assert!(
+            count == 0,
+            "expected no `{}` signal, recorded {}",
+            signal_type,
+            count
+        );
+    }
+
+    /// Recorded signals as pretty-printed JSON, for snapshot tests
+    ///
+    /// Object keys and payload parameters are sorted. `receivedAt` and `sessionID` differ
+    /// on every run and are replaced by placeholders.
+    #[must_use]
+    pub fn snapshot(&self) -> String {
+        let signals: Vec<Value> = self.lock().iter().map(snapshot_value).collect();
+        serde_json::to_string_pretty(&signals).expect("signals serialize to JSON")
+    }
+
+    fn signal_types(&self) -> Vec<String> {
+        self.lock()
+            .iter()
+            .map(|signal| signal.signal_type.clone())
+            .collect()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<Signal>> {
+        self.signals.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Transport for RecordingTransport {
+    fn post<'a>(&'a self, _url: &'a str, body: String) -> TransportFuture<'a> {
+        Box::pin(async move {
+            let signals: Vec<Signal> = serde_json::from_str(&body)?;
+            self.lock().extend(signals);
+            Ok(())
+        })
+    }
+}
+
+fn snapshot_value(signal: &Signal) -> Value {
+    let Ok(Value::Object(fields)) = serde_json::to_value(signal) else {
+        unreachable!("a signal serializes to a JSON object");
+    };
+    let mut sorted: BTreeMap<String, Value> = fields.into_iter().collect();
+    sorted.insert("receivedAt".to_string(), "[receivedAt]".into());
+    sorted.insert("sessionID".to_string(), "[sessionID]".into());
+    let mut payload = signal.payload.clone();
+    payload.sort();
+    sorted.insert("payload".to_string(), payload.into());
+    Value::Object(sorted.into_iter().collect::<Map<_, _>>())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::TelemetryDeck;
+
+    #[tokio::test]
+    async fn records_flushed_signals() {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        client.signal("userLogin").param("method", "sso").send();
+        client.send("appStarted", None, None, None, None);
+        client.flush().await.unwrap();
+
+        let signal = recorder.assert_signal_sent_with("userLogin", &[("method", "sso")]);
+        assert_eq!(signal.app_id, "1234");
+        recorder.assert_signal_sent("appStarted");
+        recorder.assert_signal_not_sent("userLogout");
+        assert_eq!(recorder.signals().len(), 2);
+
+        recorder.clear();
+        assert!(recorder.signals().is_empty());
+    }
+
+    #[tokio::test]
+    #[should_panic(expected = "expected a `userLogin` signal with [(\"method\", \"password\")]")]
+    async fn assert_signal_sent_with_reports_mismatch() {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        client
+            .signal("userLogin")
+            .param("method", "sso")
+            .send_and_wait()
+            .await
+            .unwrap();
+        recorder.assert_signal_sent_with("userLogin", &[("method", "password")]);
+    }
+
+    #[tokio::test]
+    async fn snapshot_is_stable() {
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        client
+            .signal("export")
+            .param("b", 2)
+            .param("a", 1)
+            .float(1.5)
+            .send_and_wait()
+            .await
+            .unwrap();
+
+        let version = env!("CARGO_PKG_VERSION");
+        let expected = format!(
+            r#"[
+  {{
+    "appID": "1234",
+    "clientUser": "rust",
+    "floatValue": 1.5,
+    "isTestMode": "false",
+    "payload": [
+      "a:1",
+      "b:2",
+      "telemetryClientVersion:{version}"
+    ],
+    "receivedAt": "[receivedAt]",
+    "sessionID": "[sessionID]",
+    "type": "export"
+  }}
+]"#
+        );
+        assert_eq!(recorder.snapshot(), expected);
+    }
+}
@@ -0,0 +1,310 @@
+//! Measuring durations
+//!
+//! A [`TimerGuard`] measures how long something takes and sends a signal with the
+//! elapsed seconds when it is finished or dropped. Named timers do the same without a
+//! guard, so they can be started and stopped in different places. Time is read from
+//! [`Clock::monotonic`](crate::Clock::monotonic): `Instant` on native targets and
+//! `performance.now()` with the `wasm` feature.
+
+use crate::core::TelemetryDeck;
+use crate::payload::PayloadValue;
+use crate::signals;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Running or paused measurement
+#[derive(Debug, Clone)]
+pub(crate) struct Stopwatch {
+    /// Time measured before the last pause
+    accumulated: Duration,
+    /// Monotonic time of the last start or resume, while running
+    running_since: Option<Duration>,
+}
+
+impl Stopwatch {
+    fn started(now: Duration) -> Self {
+        Stopwatch {
+            accumulated: Duration::ZERO,
+            running_since: Some(now),
+        }
+    }
+
+    fn pause(&mut self, now: Duration) {
+        if let Some(since) = self.running_since.take() {
+            self.accumulated += now.saturating_sub(since);
+        }
+    }
+
+    fn resume(&mut self, now: Duration) {
+        self.running_since.get_or_insert(now);
+    }
+
+    fn elapsed(&self, now: Duration) -> Duration {
+        let running = self
+            .running_since
+            .map_or(Duration::ZERO, |since| now.saturating_sub(since));
+        self.accumulated + running
+    }
+}
+
+/// Measures a duration and sends it when finished or dropped
+///
+/// Created with [`TelemetryDeck::start_timer`]. The signal carries the elapsed seconds
+/// both in [`signals::signal::DURATION_IN_SECONDS`] and as its float value. Time spent
+/// paused is not counted. A [`cancel`](Self::cancel)led timer sends nothing.
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::TelemetryDeck;
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+///
+/// {
+///     let _timer = client.start_timer("export.run").param("format", "csv");
+///     // ... export ...
+/// } // sends export.run with the elapsed seconds
+///
+/// let mut timer = client.start_timer("upload.run");
+/// timer.pause(); // e.g. while waiting for the user
+/// timer.resume();
+/// timer.finish();
+/// ```
+#[derive(Debug)]
+#[must_use = "the timer stops and sends its signal as soon as the guard is dropped"]
+pub struct TimerGuard {
+    client: TelemetryDeck,
+    /// `None` once the timer was finished or cancelled
+    signal_type: Option<String>,
+    params: HashMap<String, String>,
+    stopwatch: Stopwatch,
+}
+
+impl TimerGuard {
+    /// Add a parameter to the signal sent when the timer stops
+    pub fn param(mut self, key: impl Into<String>, value: impl Into<PayloadValue>) -> Self {
+        self.params.insert(key.into(), value.into().encode());
+        self
+    }
+
+    /// Stop counting time until [`resume`](Self::resume) is called
+    pub fn pause(&mut self) {
+        let now = self.client.config.clock.monotonic();
+        self.stopwatch.pause(now);
+    }
+
+    /// Count time again after a [`pause`](Self::pause)
+    pub fn resume(&mut self) {
+        let now = self.client.config.clock.monotonic();
+        self.stopwatch.resume(now);
+    }
+
+    /// Whether the timer is paused
+    pub fn is_paused(&self) -> bool {
+        self.stopwatch.running_since.is_none()
+    }
+
+    /// Time measured so far
+    pub fn elapsed(&self) -> Duration {
+        self.stopwatch.elapsed(self.client.config.clock.monotonic())
+    }
+
+    /// Stop the timer and send its signal
+    pub fn finish(mut self) {
+        self.stop();
+    }
+
+    /// Stop the timer without sending anything
+    pub fn cancel(mut self) {
+        self.signal_type = None;
+    }
+
+    fn stop(&mut self) {
+        if let Some(signal_type) = self.signal_type.take() {
+            let elapsed = self.elapsed();
+            send_duration(
+                &self.client,
+                &signal_type,
+                std::mem::take(&mut self.params),
+                elapsed,
+            );
+        }
+    }
+}
+
+impl Drop for TimerGuard {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl TelemetryDeck {
+    /// Start measuring a duration reported as a `signal_type` signal
+    ///
+    /// See [`TimerGuard`].
+    pub fn start_timer(&self, signal_type: &str) -> TimerGuard {
+        TimerGuard {
+            client: self.clone(),
+            signal_type: Some(signal_type.to_string()),
+            params: HashMap::new(),
+            stopwatch: Stopwatch::started(self.config.clock.monotonic()),
+        }
+    }
+
+    /// Start a timer that is stopped by its signal type with [`stop_timer`](Self::stop_timer)
+    ///
+    /// Named timers are shared by all clones of the client, so they can be stopped
+    /// somewhere else than where they were started. Starting a timer with the name of a
+    /// running one restarts it.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use telemetrydeck_wasm::TelemetryDeck;
+    ///
+    /// let client = TelemetryDeck::new("YOUR-APP-ID");
+    /// client.start_named_timer("onboarding.completed");
+    ///
+    /// // later, in another part of the application
+    /// client.stop_timer("onboarding.completed");
+    /// ```
+    pub fn start_named_timer(&self, signal_type: &str) {
+        let stopwatch = Stopwatch::started(self.config.clock.monotonic());
+        self.write_state()
+            .timers
+            .insert(signal_type.to_string(), stopwatch);
+    }
+
+    /// Pause the named timer `signal_type`, returning whether it exists
+    pub fn pause_timer(&self, signal_type: &str) -> bool {
+        let now = self.config.clock.monotonic();
+        let mut state = self.write_state();
+        state
+            .timers
+            .get_mut(signal_type)
+            .map(|stopwatch| stopwatch.pause(now))
+            .is_some()
+    }
+
+    /// Resume the named timer `signal_type`, returning whether it exists
+    pub fn resume_timer(&self, signal_type: &str) -> bool {
+        let now = self.config.clock.monotonic();
+        let mut state = self.write_state();
+        state
+            .timers
+            .get_mut(signal_type)
+            .map(|stopwatch| stopwatch.resume(now))
+            .is_some()
+    }
+
+    /// Stop the named timer `signal_type` and send its signal, returning whether it
+    /// existed
+    pub fn stop_timer(&self, signal_type: &str) -> bool {
+        let now = self.config.clock.monotonic();
+        let stopwatch = self.write_state().timers.remove(signal_type);
+        match stopwatch {
+            Some(stopwatch) => {
+                send_duration(self, signal_type, HashMap::new(), stopwatch.elapsed(now));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop the named timer `signal_type` without sending anything, returning whether it
+    /// existed
+    pub fn cancel_timer(&self, signal_type: &str) -> bool {
+        self.write_state().timers.remove(signal_type).is_some()
+    }
+}
+
+/// Send `signal_type` with `elapsed` as its duration parameter and float value
+fn send_duration(
+    client: &TelemetryDeck,
+    signal_type: &str,
+    params: HashMap<String, String>,
+    elapsed: Duration,
+) {
+    let seconds = elapsed.as_secs_f64();
+    client
+        .signal(signal_type)
+        .params(params)
+        .param(signals::signal::DURATION_IN_SECONDS, seconds)
+        .float(seconds)
+        .send();
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::RecordingTransport;
+    use crate::{FixedClock, TelemetryDeck, signals};
+    use chrono::{TimeZone, Utc};
+    use std::time::Duration;
+
+    fn recorded_client() -> (TelemetryDeck, RecordingTransport, FixedClock) {
+        let clock = FixedClock::new(Utc.with_ymd_and_hms(2025, 1, 15, 10, 30, 0).unwrap());
+        let (client, recorder) = TelemetryDeck::new_recording("1234");
+        (client.with_clock(clock.clone()), recorder, clock)
+    }
+
+    #[tokio::test]
+    async fn guard_sends_elapsed_time_without_pauses() {
+        let (client, recorder, clock) = recorded_client();
+        {
+            let mut timer = client.start_timer("export.run").param("format", "csv");
+            clock.advance(Duration::from_secs(2));
+            timer.pause();
+            assert!(timer.is_paused());
+            clock.advance(Duration::from_secs(10));
+            timer.resume();
+            clock.advance(Duration::from_millis(500));
+            assert_eq!(timer.elapsed(), Duration::from_millis(2500));
+        }
+        client.flush().await.unwrap();
+
+        let signal = recorder.assert_signal_sent_with(
+            "export.run",
+            &[
+                (signals::signal::DURATION_IN_SECONDS, "2.5"),
+                ("format", "csv"),
+            ],
+        );
+        assert_eq!(signal.float_value, Some(2.5));
+    }
+
+    #[tokio::test]
+    async fn finished_and_cancelled_guards_send_once_or_never() {
+        let (client, recorder, clock) = recorded_client();
+        let timer = client.start_timer("export.run");
+        clock.advance(Duration::from_secs(1));
+        timer.finish();
+        client.start_timer("import.run").cancel();
+        client.flush().await.unwrap();
+
+        assert_eq!(recorder.signals_of_type("export.run").len(), 1);
+        recorder.assert_signal_not_sent("import.run");
+    }
+
+    #[tokio::test]
+    async fn named_timers_are_stopped_by_key() {
+        let (client, recorder, clock) = recorded_client();
+        client.start_named_timer("onboarding.completed");
+        clock.advance(Duration::from_secs(3));
+        assert!(client.clone().pause_timer("onboarding.completed"));
+        clock.advance(Duration::from_secs(60));
+        assert!(client.resume_timer("onboarding.completed"));
+        clock.advance(Duration::from_secs(1));
+
+        assert!(client.clone().stop_timer("onboarding.completed"));
+        assert!(!client.stop_timer("onboarding.completed"));
+        client.start_named_timer("tutorial.completed");
+        assert!(client.cancel_timer("tutorial.completed"));
+        client.flush().await.unwrap();
+
+        recorder.assert_signal_sent_with(
+            "onboarding.completed",
+            &[(signals::signal::DURATION_IN_SECONDS, "4.0")],
+        );
+        recorder.assert_signal_not_sent("tutorial.completed");
+    }
+}
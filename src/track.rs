@@ -0,0 +1,188 @@
+use crate::core::TelemetryDeck;
+use crate::error::TelemetryDeckError;
+use crate::signal_builder::SignalBuilder;
+use std::collections::HashMap;
+
+/// A strongly typed signal definition
+///
+/// Usually implemented with `#[derive(TelemetrySignal)]` (requires the `derive` feature),
+/// which generates the signal type, payload keys and float value at compile time:
+///
+/// ```no_run
+/// # #[cfg(feature = "derive")]
+/// # fn main() {
+/// use telemetrydeck_wasm::{TelemetryDeck, TelemetrySignal};
+///
+/// #[derive(TelemetrySignal)]
+/// #[signal(type = "App.checkout")]
+/// struct Checkout {
+///     #[signal(float_value)]
+///     total: f64,
+///     #[signal(param = "TelemetryDeck.Purchase.currencyCode")]
+///     currency: String,
+///     items: u32,
+///     coupon: Option<String>,
+/// }
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+/// client.track(Checkout {
+///     total: 42.0,
+///     currency: "EUR".to_string(),
+///     items: 3,
+///     coupon: None,
+/// });
+/// # }
+/// # #[cfg(not(feature = "derive"))]
+/// # fn main() {}
+/// ```
+///
+/// It can also be implemented by hand:
+///
+/// ```no_run
+/// use std::collections::HashMap;
+/// use telemetrydeck_wasm::{TelemetryDeck, TelemetrySignal};
+///
+/// struct AppOpened {
+///     cold_start: bool,
+/// }
+///
+/// impl TelemetrySignal for AppOpened {
+///     const SIGNAL_TYPE: &'static str = "App.opened";
+///
+///     fn payload(&self) -> HashMap<String, String> {
+///         HashMap::from([("coldStart".to_string(), self.cold_start.to_string())])
+///     }
+/// }
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+/// client.track(AppOpened { cold_start: true });
+/// ```
+pub trait TelemetrySignal {
+    /// The signal type sent to TelemetryDeck
+    const SIGNAL_TYPE: &'static str;
+
+    /// Payload parameters of this signal
+    fn payload(&self) -> HashMap<String, String>;
+
+    /// Floating-point value of this signal
+    fn float_value(&self) -> Option<f64> {
+        None
+    }
+}
+
+impl<T: TelemetrySignal + ?Sized> TelemetrySignal for &T {
+    const SIGNAL_TYPE: &'static str = T::SIGNAL_TYPE;
+
+    fn payload(&self) -> HashMap<String, String> {
+        (**self).payload()
+    }
+
+    fn float_value(&self) -> Option<f64> {
+        (**self).float_value()
+    }
+}
+
+impl TelemetryDeck {
+    /// Start building a signal from a typed definition
+    ///
+    /// Use this to set the user or other per-signal settings before sending.
+    pub fn signal_for<T: TelemetrySignal>(&self, event: T) -> SignalBuilder<'_> {
+        let builder = self.signal(T::SIGNAL_TYPE).params(event.payload());
+        match event.float_value() {
+            Some(value) => builder.float(value),
+            None => builder,
+        }
+    }
+
+    /// Send a typed signal (fire-and-forget)
+    ///
+    /// Behaves like [`TelemetryDeck::send`].
+    pub fn track<T: TelemetrySignal>(&self, event: T) {
+        self.signal_for(event).send();
+    }
+
+    /// Send a typed signal right away and wait for the result
+    ///
+    /// Behaves like [`TelemetryDeck::send_sync`].
+    pub async fn track_and_wait<T: TelemetrySignal>(
+        &self,
+        event: T,
+    ) -> Result<(), TelemetryDeckError> {
+        self.signal_for(event).send_and_wait().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{TelemetryDeck, TelemetrySignal};
+
+    #[derive(telemetrydeck_wasm_derive::TelemetrySignal)]
+    #[signal(type = "App.checkout")]
+    struct Checkout {
+        #[signal(float_value)]
+        total: f64,
+        #[signal(param = "TelemetryDeck.Purchase.currencyCode")]
+        currency: String,
+        items: u32,
+        coupon: Option<String>,
+        #[signal(skip)]
+        #[allow(dead_code)]
+        internal_id: u64,
+    }
+
+    #[derive(telemetrydeck_wasm_derive::TelemetrySignal)]
+    struct AppOpened;
+
+    fn checkout() -> Checkout {
+        Checkout {
+            total: 42.5,
+            currency: "EUR".to_string(),
+            items: 3,
+            coupon: None,
+            internal_id: 7,
+        }
+    }
+
+    #[test]
+    fn derive_generates_signal_type_payload_and_float_value() {
+        let event = checkout();
+        assert_eq!(Checkout::SIGNAL_TYPE, "App.checkout");
+        assert_eq!(event.float_value(), Some(42.5));
+
+        let mut payload: Vec<_> = event.payload().into_iter().collect();
+        payload.sort();
+        assert_eq!(
+            payload,
+            vec![
+                (
+                    "TelemetryDeck.Purchase.currencyCode".to_string(),
+                    "EUR".to_string()
+                ),
+                ("items".to_string(), "3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn derive_defaults_signal_type_to_struct_name() {
+        assert_eq!(AppOpened::SIGNAL_TYPE, "AppOpened");
+        assert_eq!(AppOpened.float_value(), None);
+        assert!(AppOpened.payload().is_empty());
+    }
+
+    #[test]
+    fn signal_for_builds_signal() {
+        let client = TelemetryDeck::new("1234");
+        let event = checkout();
+        let result = client.signal_for(&event).user("clientUser").build();
+        assert_eq!(result.float_value, event.float_value());
+        assert_eq!(result.signal_type, "App.checkout");
+        assert_eq!(result.float_value, Some(42.5));
+        assert!(
+            result
+                .payload
+                .contains(&"TelemetryDeck.Purchase.currencyCode:EUR".to_string())
+        );
+        assert_ne!(result.client_user, "rust");
+    }
+}
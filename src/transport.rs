@@ -0,0 +1,169 @@
+//! Pluggable HTTP layer used to post batches of signals
+//!
+//! The client serializes every batch to JSON and hands it to a [`Transport`] together
+//! with the ingestion URL. Retries, batching and the offline spool are handled by the
+//! client, based on the error the transport returns.
+//!
+//! The default transport is `ReqwestTransport` on native targets and `ReqwasmTransport`
+//! with the `wasm` feature.
+
+use crate::error::TelemetryDeckError;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Future returned by [`Transport::post`]
+///
+/// It is `Send` on native targets, so that deliveries can run on `tokio::spawn`.
+#[cfg(not(feature = "wasm"))]
+pub type TransportFuture<'a> =
+    Pin<Box<dyn Future<Output = Result<(), TelemetryDeckError>> + Send + 'a>>;
+
+/// Future returned by [`Transport::post`]
+#[cfg(feature = "wasm")]
+pub type TransportFuture<'a> = Pin<Box<dyn Future<Output = Result<(), TelemetryDeckError>> + 'a>>;
+
+/// Delivers serialized batches of signals to TelemetryDeck
+///
+/// Implementations post `body`, a JSON array of signals, to `url` with the
+/// `Content-Type: application/json` header and report the outcome:
+///
+/// - `Ok(())` when the server answered with a `2xx` status
+/// - [`TelemetryDeckError::HttpStatus`] for any other status, including the
+///   `Retry-After` delay when the server sent one
+/// - [`TelemetryDeckError::Transport`] when the request could not be completed
+///
+/// The client decides whether to retry based on
+/// [`TelemetryDeckError::is_retryable`], so transports should not retry on their own.
+///
+/// # Examples
+///
+/// ```no_run
+/// use telemetrydeck_wasm::{TelemetryDeck, Transport, TransportFuture};
+///
+/// /// Prints signals instead of sending them
+/// #[derive(Debug)]
+/// struct ConsoleTransport;
+///
+/// impl Transport for ConsoleTransport {
+///     fn post<'a>(&'a self, url: &'a str, body: String) -> TransportFuture<'a> {
+///         Box::pin(async move {
+///             println!("POST {url}: {body}");
+///             Ok(())
+///         })
+///     }
+/// }
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID").with_transport(ConsoleTransport);
+/// client.send("appStarted", None, None, None, None);
+/// ```
+pub trait Transport: fmt::Debug + Send + Sync {
+    /// Post a serialized batch of signals to `url`
+    fn post<'a>(&'a self, url: &'a str, body: String) -> TransportFuture<'a>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn post<'a>(&'a self, url: &'a str, body: String) -> TransportFuture<'a> {
+        (**self).post(url, body)
+    }
+}
+
+/// Settings of the default transport configured through the builder
+#[derive(Debug, Clone, Default)]
+pub(crate) struct HttpConfig {
+    /// Timeout for a single request
+    pub(crate) timeout: Option<Duration>,
+    /// `User-Agent` header, platform default when `None`
+    pub(crate) user_agent: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Transport, TransportFuture};
+    use crate::{RetryPolicy, TelemetryDeck, TelemetryDeckError};
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    /// Records requests and answers with the scripted statuses, then `200`
+    #[derive(Debug, Default)]
+    struct FakeTransport {
+        requests: Mutex<Vec<(String, String)>>,
+        statuses: Mutex<Vec<u16>>,
+    }
+
+    impl Transport for FakeTransport {
+        fn post<'a>(&'a self, url: &'a str, body: String) -> TransportFuture<'a> {
+            Box::pin(async move {
+                self.requests.lock().unwrap().push((url.to_string(), body));
+                let mut statuses = self.statuses.lock().unwrap();
+                match statuses.is_empty() {
+                    true => Ok(()),
+                    false => Err(TelemetryDeckError::HttpStatus {
+                        status: statuses.remove(0),
+                        body: String::new(),
+                        retry_after: None,
+                    }),
+                }
+            })
+        }
+    }
+
+    fn client_with(transport: FakeTransport) -> (TelemetryDeck, Arc<FakeTransport>) {
+        let transport = Arc::new(transport);
+        let client = TelemetryDeck::builder("1234")
+            .namespace("tenant")
+            .retry_policy(RetryPolicy {
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            })
+            .transport(Arc::clone(&transport))
+            .build()
+            .unwrap();
+        (client, transport)
+    }
+
+    #[tokio::test]
+    async fn custom_transport_receives_serialized_batch() {
+        let (client, transport) = client_with(FakeTransport::default());
+        client
+            .send_sync("signal_type", None, None, None, None)
+            .await
+            .unwrap();
+
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(
+            requests[0].0,
+            "https://nom.telemetrydeck.com/v2/namespace/tenant/"
+        );
+        let signals: serde_json::Value = serde_json::from_str(&requests[0].1).unwrap();
+        assert_eq!(signals[0]["type"], "signal_type");
+    }
+
+    #[tokio::test]
+    async fn transport_errors_drive_retries() {
+        let (client, transport) = client_with(FakeTransport {
+            statuses: Mutex::new(vec![503, 503]),
+            ..FakeTransport::default()
+        });
+        client.send("signal_type", None, None, None, None);
+        client.flush().await.unwrap();
+        assert_eq!(transport.requests.lock().unwrap().len(), 3);
+
+        let (client, transport) = client_with(FakeTransport {
+            statuses: Mutex::new(vec![400]),
+            ..FakeTransport::default()
+        });
+        let result = client
+            .send_sync("signal_type", None, None, None, None)
+            .await;
+        assert!(matches!(
+            result,
+            Err(TelemetryDeckError::HttpStatus { status: 400, .. })
+        ));
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+    }
+}
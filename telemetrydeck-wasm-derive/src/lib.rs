@@ -0,0 +1,167 @@
+//! Derive macro for strongly typed [telemetrydeck-wasm](https://docs.rs/telemetrydeck-wasm) signals
+//!
+//! This crate is re-exported by `telemetrydeck-wasm` when its `derive` feature is enabled;
+//! use it through `telemetrydeck_wasm::TelemetrySignal` rather than depending on it directly.
+//!
+//! # Attributes
+//!
+//! On the struct:
+//!
+//! - `#[signal(type = "App.checkout")]` - the signal type. Defaults to the struct name.
+//!
+//! On fields:
+//!
+//! - `#[signal(param = "TelemetryDeck.Purchase.currencyCode")]` - payload key for the field.
+//!   Defaults to the field name.
+//! - `#[signal(float_value)]` - send the field as the signal's `floatValue` instead of a
+//!   payload parameter.
+//! - `#[signal(skip)]` - leave the field out of the signal.
+//!
+//! Payload fields must implement `telemetrydeck_wasm::payload::ToPayloadValue`; the
+//! `float_value` field must be a number or an `Option` of one.
+
+#![deny(missing_docs)]
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input};
+
+/// Implement `telemetrydeck_wasm::TelemetrySignal` for a struct with named fields
+///
+/// ```ignore
+/// use telemetrydeck_wasm::{TelemetryDeck, TelemetrySignal, params};
+///
+/// #[derive(TelemetrySignal)]
+/// #[signal(type = "App.checkout")]
+/// struct Checkout {
+///     #[signal(float_value)]
+///     total: f64,
+///     #[signal(param = "TelemetryDeck.Purchase.currencyCode")]
+///     currency: String,
+///     items: u32,
+/// }
+///
+/// let client = TelemetryDeck::new("YOUR-APP-ID");
+/// client.track(Checkout { total: 42.0, currency: "EUR".into(), items: 3 });
+/// ```
+#[proc_macro_derive(TelemetrySignal, attributes(signal))]
+pub fn derive_telemetry_signal(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut signal_type = LitStr::new(&name.to_string(), name.span());
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("signal")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type") {
+                signal_type = meta.value()?.parse()?;
+                if signal_type.value().is_empty() {
+                    return Err(meta.error("signal type must not be empty"));
+                }
+                Ok(())
+            } else {
+                Err(meta.error("expected `type = \"...\"`"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            Fields::Unit => &syn::punctuated::Punctuated::new(),
+            Fields::Unnamed(_) => {
+                return Err(syn::Error::new_spanned(
+                    name,
+                    "TelemetrySignal can only be derived for structs with named fields",
+                ));
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                name,
+                "TelemetrySignal can only be derived for structs",
+            ));
+        }
+    };
+
+    let mut params = Vec::new();
+    let mut float_value: Option<TokenStream2> = None;
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let mut key = LitStr::new(&ident.to_string(), ident.span());
+        let mut is_float_value = false;
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("signal")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("param") {
+                    key = meta.value()?.parse()?;
+                    if key.value().is_empty() {
+                        return Err(meta.error("param name must not be empty"));
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("float_value") {
+                    is_float_value = true;
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `param = \"...\"`, `float_value` or `skip`"))
+                }
+            })?;
+        }
+
+        if skip {
+            continue;
+        }
+        if is_float_value {
+            if float_value.is_some() {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "only one field can be marked `#[signal(float_value)]`",
+                ));
+            }
+            float_value = Some(quote! {
+                ::telemetrydeck_wasm::__private::FloatValue::float_value(&self.#ident)
+            });
+        } else {
+            params.push(quote! {
+                if let ::std::option::Option::Some(value) =
+                    ::telemetrydeck_wasm::payload::ToPayloadValue::to_payload_value(&self.#ident)
+                {
+                    payload.insert(::std::string::String::from(#key), value.encode());
+                }
+            });
+        }
+    }
+
+    let float_value = float_value.map(|value| {
+        quote! {
+            fn float_value(&self) -> ::std::option::Option<f64> {
+                #value
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::telemetrydeck_wasm::TelemetrySignal for #name #ty_generics #where_clause {
+            const SIGNAL_TYPE: &'static str = #signal_type;
+
+            fn payload(&self) -> ::std::collections::HashMap<::std::string::String, ::std::string::String> {
+                #[allow(unused_mut)]
+                let mut payload = ::std::collections::HashMap::new();
+                #(#params)*
+                payload
+            }
+
+            #float_value
+        }
+    })
+}